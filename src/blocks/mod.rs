@@ -6,6 +6,7 @@ use crate::world::World;
 use redstone::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignBlockEntity {
@@ -25,7 +26,7 @@ impl BlockEntity {
         let mut fullness_sum: f32 = 0.0;
         for item in slots_nbt {
             let item_compound = nbt_unwrap_val!(item, Value::Compound);
-            let count = nbt_unwrap_val!(item_compound["Count"], Value::Byte);
+            let count = *nbt_unwrap_val!(item_compound.get("Count")?, Value::Byte);
             let namespaced_name = nbt_unwrap_val!(
                 item_compound
                     .get("Id")
@@ -45,25 +46,25 @@ impl BlockEntity {
         let id = nbt_unwrap_val!(&nbt.get("Id").or_else(|| nbt.get("id"))?, Value::String);
         match id.as_ref() {
             "minecraft:comparator" => Some(BlockEntity::Comparator {
-                output_strength: *nbt_unwrap_val!(&nbt["OutputSignal"], Value::Int) as u8,
+                output_strength: *nbt_unwrap_val!(nbt.get("OutputSignal")?, Value::Int) as u8,
             }),
             "minecraft:furnace" => {
-                BlockEntity::load_container(nbt_unwrap_val!(&nbt["Items"], Value::List), 3)
+                BlockEntity::load_container(nbt_unwrap_val!(nbt.get("Items")?, Value::List), 3)
             }
             "minecraft:barrel" => {
-                BlockEntity::load_container(nbt_unwrap_val!(&nbt["Items"], Value::List), 27)
+                BlockEntity::load_container(nbt_unwrap_val!(nbt.get("Items")?, Value::List), 27)
             }
             "minecraft:hopper" => {
-                BlockEntity::load_container(nbt_unwrap_val!(&nbt["Items"], Value::List), 5)
+                BlockEntity::load_container(nbt_unwrap_val!(nbt.get("Items")?, Value::List), 5)
             }
             "minecraft:sign" => Some({
                 BlockEntity::Sign(Box::new(SignBlockEntity {
                     rows: [
                         // This cloning is really dumb
-                        nbt_unwrap_val!(nbt["Text1"].clone(), Value::String),
-                        nbt_unwrap_val!(nbt["Text2"].clone(), Value::String),
-                        nbt_unwrap_val!(nbt["Text3"].clone(), Value::String),
-                        nbt_unwrap_val!(nbt["Text4"].clone(), Value::String),
+                        nbt_unwrap_val!(nbt.get("Text1")?.clone(), Value::String),
+                        nbt_unwrap_val!(nbt.get("Text2")?.clone(), Value::String),
+                        nbt_unwrap_val!(nbt.get("Text3")?.clone(), Value::String),
+                        nbt_unwrap_val!(nbt.get("Text4")?.clone(), Value::String),
                     ],
                 }))
             }),
@@ -71,6 +72,11 @@ impl BlockEntity {
         }
     }
 
+    /// Returns the data the client needs to render this block entity.
+    ///
+    /// Only signs have any at the moment. Comparators and containers (barrels, furnaces and
+    /// hoppers) keep their state on the server, so nothing is sent for them. If containers ever
+    /// get their items synced to the client they will need to be added here as well.
     pub fn to_nbt(&self, pos: BlockPos) -> Option<nbt::Blob> {
         use nbt::Value;
         let blob = match self {
@@ -93,6 +99,20 @@ impl BlockEntity {
             nbt
         })
     }
+
+    /// Returns true if this block entity can be kept when the block it belongs to is changed to
+    /// `block`. Containers only keep track of how full they are, so they can move between
+    /// barrels, furnaces and hoppers. Signs can move between standing and wall signs.
+    pub fn fits_block(&self, block: Block) -> bool {
+        match self {
+            BlockEntity::Comparator { .. } => matches!(block, Block::RedstoneComparator { .. }),
+            BlockEntity::Container { .. } => matches!(
+                block,
+                Block::Barrel { .. } | Block::Furnace { .. } | Block::Hopper { .. }
+            ),
+            BlockEntity::Sign(_) => matches!(block, Block::Sign { .. } | Block::WallSign { .. }),
+        }
+    }
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize, Hash)]
@@ -147,6 +167,18 @@ impl std::ops::Sub for BlockPos {
     }
 }
 
+impl std::ops::Add for BlockPos {
+    type Output = BlockPos;
+
+    fn add(self, rhs: BlockPos) -> BlockPos {
+        BlockPos {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum BlockDirection {
     North,
@@ -214,7 +246,7 @@ impl BlockDirection {
         }
     }
 
-    fn rotate(self) -> BlockDirection {
+    pub fn rotate(self) -> BlockDirection {
         use BlockDirection::*;
         match self {
             North => East,
@@ -224,7 +256,7 @@ impl BlockDirection {
         }
     }
 
-    fn rotate_ccw(self) -> BlockDirection {
+    pub fn rotate_ccw(self) -> BlockDirection {
         use BlockDirection::*;
         match self {
             North => West,
@@ -310,6 +342,18 @@ impl BlockFacing {
         }
     }
 
+    pub fn opposite(self) -> BlockFacing {
+        use BlockFacing::*;
+        match self {
+            North => South,
+            South => North,
+            East => West,
+            West => East,
+            Up => Down,
+            Down => Up,
+        }
+    }
+
     pub fn offset_pos(self, mut pos: BlockPos, n: i32) -> BlockPos {
         match self {
             BlockFacing::North => pos.z -= n,
@@ -413,6 +457,140 @@ impl Block {
         )
     }
 
+    /// Rotates the block 90 degrees clockwise when looking down on it.
+    pub fn rotate(self) -> Block {
+        self.map_directions(BlockDirection::rotate, |rotation| (rotation + 4) % 16)
+    }
+
+    /// Mirrors the block so that it faces the other way along the axis of `direction`.
+    pub fn flip(self, direction: BlockDirection) -> Block {
+        let flip_direction = |facing: BlockDirection| {
+            if facing == direction || facing == direction.opposite() {
+                facing.opposite()
+            } else {
+                facing
+            }
+        };
+        // Sign rotations go clockwise from south in 16 steps
+        match direction {
+            BlockDirection::East | BlockDirection::West => {
+                self.map_directions(flip_direction, |rotation| (16 - rotation) % 16)
+            }
+            BlockDirection::North | BlockDirection::South => {
+                self.map_directions(flip_direction, |rotation| (24 - rotation) % 16)
+            }
+        }
+    }
+
+    /// Turns the block upside down. Slabs are always bottom slabs and there are no stairs or
+    /// trapdoors, so this only changes blocks attached to the floor or ceiling and observers.
+    pub fn flip_vertical(self) -> Block {
+        match self {
+            Block::Lever { mut lever } => {
+                lever.face = match lever.face {
+                    LeverFace::Floor => LeverFace::Ceiling,
+                    LeverFace::Ceiling => LeverFace::Floor,
+                    LeverFace::Wall => LeverFace::Wall,
+                };
+                Block::Lever { lever }
+            }
+            Block::StoneButton { mut button } => {
+                button.face = match button.face {
+                    ButtonFace::Floor => ButtonFace::Ceiling,
+                    ButtonFace::Ceiling => ButtonFace::Floor,
+                    ButtonFace::Wall => ButtonFace::Wall,
+                };
+                Block::StoneButton { button }
+            }
+            Block::Observer {
+                facing: BlockFacing::Up,
+            } => Block::Observer {
+                facing: BlockFacing::Down,
+            },
+            Block::Observer {
+                facing: BlockFacing::Down,
+            } => Block::Observer {
+                facing: BlockFacing::Up,
+            },
+            _ => self,
+        }
+    }
+
+    /// Maps every horizontal direction of the block. Slabs are always bottom slabs and there
+    /// are no stairs, trapdoors or rails, so they don't need to be handled here.
+    fn map_directions(
+        self,
+        map: impl Fn(BlockDirection) -> BlockDirection,
+        map_sign_rotation: impl Fn(u32) -> u32,
+    ) -> Block {
+        match self {
+            Block::RedstoneWire { wire } => {
+                let mut new_wire = wire;
+                for (direction, side) in [
+                    (BlockDirection::North, wire.north),
+                    (BlockDirection::South, wire.south),
+                    (BlockDirection::East, wire.east),
+                    (BlockDirection::West, wire.west),
+                ]
+                .iter()
+                {
+                    match map(*direction) {
+                        BlockDirection::North => new_wire.north = *side,
+                        BlockDirection::South => new_wire.south = *side,
+                        BlockDirection::East => new_wire.east = *side,
+                        BlockDirection::West => new_wire.west = *side,
+                    }
+                }
+                Block::RedstoneWire { wire: new_wire }
+            }
+            Block::WallSign { sign_type, facing } => Block::WallSign {
+                sign_type,
+                facing: map(facing),
+            },
+            Block::Sign {
+                sign_type,
+                rotation,
+            } => Block::Sign {
+                sign_type,
+                rotation: map_sign_rotation(rotation),
+            },
+            Block::Lever { mut lever } => {
+                lever.facing = map(lever.facing);
+                Block::Lever { lever }
+            }
+            Block::StoneButton { mut button } => {
+                button.facing = map(button.facing);
+                Block::StoneButton { button }
+            }
+            Block::RedstoneWallTorch { lit, facing } => Block::RedstoneWallTorch {
+                lit,
+                facing: map(facing),
+            },
+            Block::RedstoneRepeater { mut repeater } => {
+                repeater.facing = map(repeater.facing);
+                Block::RedstoneRepeater { repeater }
+            }
+            Block::RedstoneComparator { mut comparator } => {
+                comparator.facing = map(comparator.facing);
+                Block::RedstoneComparator { comparator }
+            }
+            Block::TripwireHook { direction } => Block::TripwireHook {
+                direction: map(direction),
+            },
+            Block::Observer { facing } => {
+                let facing = match facing {
+                    BlockFacing::North => map(BlockDirection::North).block_facing(),
+                    BlockFacing::South => map(BlockDirection::South).block_facing(),
+                    BlockFacing::East => map(BlockDirection::East).block_facing(),
+                    BlockFacing::West => map(BlockDirection::West).block_facing(),
+                    vertical => vertical,
+                };
+                Block::Observer { facing }
+            }
+            _ => self,
+        }
+    }
+
     fn has_comparator_override(self) -> bool {
         matches!(
             self,
@@ -715,7 +893,8 @@ impl Block {
         }
     }
 
-    fn update(self, world: &mut impl World, pos: BlockPos) {
+    /// Called when a neighboring block has been updated.
+    pub fn update(self, world: &mut impl World, pos: BlockPos) {
         match self {
             Block::RedstoneWire { wire } => {
                 wire.on_neighbor_updated(world, pos);
@@ -1002,6 +1181,335 @@ impl Block {
             _ => {}
         }
     }
+
+    /// All the values `set_property` accepts for the property `key` of this block. This is
+    /// empty if the block doesn't have the property.
+    pub fn property_values(self, key: &str) -> &'static [&'static str] {
+        const BOOLS: &[&str] = &["false", "true"];
+        const DIRECTIONS: &[&str] = &["north", "south", "east", "west"];
+        const FACES: &[&str] = &["floor", "wall", "ceiling"];
+        const SIXTEEN: &[&str] = &[
+            "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15",
+        ];
+        match (self, key) {
+            (Block::RedstoneWire { .. }, "north" | "south" | "east" | "west") => {
+                &["none", "side", "up"]
+            }
+            (Block::RedstoneWire { .. }, "power") => SIXTEEN,
+            (Block::RedstoneLamp { .. }, "lit") => BOOLS,
+            (Block::RedstoneTorch { .. }, "lit") => BOOLS,
+            (Block::RedstoneWallTorch { .. }, "lit") => BOOLS,
+            (Block::RedstoneWallTorch { .. }, "facing") => DIRECTIONS,
+            (Block::RedstoneRepeater { .. }, "facing") => DIRECTIONS,
+            (Block::RedstoneRepeater { .. }, "delay") => &["1", "2", "3", "4"],
+            (Block::RedstoneRepeater { .. }, "powered" | "locked") => BOOLS,
+            (Block::RedstoneComparator { .. }, "facing") => DIRECTIONS,
+            (Block::RedstoneComparator { .. }, "mode") => &["compare", "subtract"],
+            (Block::RedstoneComparator { .. }, "powered") => BOOLS,
+            (Block::Lever { .. }, "face") => FACES,
+            (Block::Lever { .. }, "facing") => DIRECTIONS,
+            (Block::Lever { .. }, "powered") => BOOLS,
+            (Block::StoneButton { .. }, "face") => FACES,
+            (Block::StoneButton { .. }, "facing") => DIRECTIONS,
+            (Block::StoneButton { .. }, "powered") => BOOLS,
+            (Block::TripwireHook { .. }, "facing") => DIRECTIONS,
+            (Block::Observer { .. }, "facing") => &["north", "south", "east", "west", "up", "down"],
+            (Block::WallSign { .. }, "facing") => DIRECTIONS,
+            (Block::Sign { .. }, "rotation") => SIXTEEN,
+            _ => &[],
+        }
+    }
+
+    /// The current value of the property `key`, or `None` if the block doesn't have it.
+    pub fn property(self, key: &str) -> Option<&'static str> {
+        // Every value of a property results in a different block, so the current value is the
+        // one that doesn't change the block
+        self.property_values(key).iter().copied().find(|val| {
+            let mut block = self;
+            block.set_property(key, val);
+            block == self
+        })
+    }
+
+    /// The names of all the properties `set_property` knows about.
+    const PROPERTY_NAMES: &'static [&'static str] = &[
+        "north", "south", "east", "west", "power", "lit", "facing", "delay", "powered", "locked",
+        "mode", "face", "rotation",
+    ];
+
+    /// All the properties the block has and their current values.
+    pub fn properties(self) -> Vec<(&'static str, &'static str)> {
+        Block::PROPERTY_NAMES
+            .iter()
+            .filter_map(|key| Some((*key, self.property(key)?)))
+            .collect()
+    }
+
+    /// The ids of every state of this block, no matter the values of its properties. Vanilla
+    /// gives all the states of a block ids right next to each other.
+    pub fn state_ids(self) -> RangeInclusive<u32> {
+        let mut states = vec![self];
+        for key in Block::PROPERTY_NAMES {
+            let values = self.property_values(key);
+            if values.is_empty() {
+                continue;
+            }
+            states = states
+                .into_iter()
+                .flat_map(|state| {
+                    values.iter().map(move |val| {
+                        let mut state = state;
+                        state.set_property(key, val);
+                        state
+                    })
+                })
+                .collect();
+        }
+        let ids = states.iter().map(|state| state.get_id());
+        ids.clone().min().unwrap()..=ids.max().unwrap()
+    }
+
+    /// The name `from_name` would give this block, not including its properties.
+    pub fn get_name(self) -> Option<&'static str> {
+        Block::NAMES.iter().copied().find(|name| {
+            let mut block = Block::from_name(name).unwrap();
+            block.copy_properties(self);
+            block == self
+        })
+    }
+
+    /// Copies every property that `other` has and that this block can take the value of.
+    pub fn copy_properties(&mut self, other: Block) {
+        for key in Block::PROPERTY_NAMES {
+            if let Some(val) = other.property(key) {
+                if self.property_values(key).contains(&val) {
+                    self.set_property(key, val);
+                }
+            }
+        }
+    }
+
+    /// The block tags of the vanilla data pack, limited to the blocks that the server knows
+    /// about. The entries are names that can be passed to `Block::from_name`.
+    pub const TAGS: &'static [(&'static str, &'static [&'static str])] = &[
+        ("buttons", &["stone_button"]),
+        (
+            "impermeable",
+            &[
+                "glass",
+                "white_stained_glass",
+                "orange_stained_glass",
+                "magenta_stained_glass",
+                "light_blue_stained_glass",
+                "yellow_stained_glass",
+                "lime_stained_glass",
+                "pink_stained_glass",
+                "gray_stained_glass",
+                "light_gray_stained_glass",
+                "cyan_stained_glass",
+                "purple_stained_glass",
+                "blue_stained_glass",
+                "brown_stained_glass",
+                "green_stained_glass",
+                "red_stained_glass",
+                "black_stained_glass",
+            ],
+        ),
+        ("pressure_plates", &["stone_pressure_plate"]),
+        (
+            "signs",
+            &[
+                "oak_sign",
+                "spruce_sign",
+                "birch_sign",
+                "jungle_sign",
+                "acacia_sign",
+                "dark_oak_sign",
+                "oak_wall_sign",
+                "spruce_wall_sign",
+                "birch_wall_sign",
+                "jungle_wall_sign",
+                "acacia_wall_sign",
+                "dark_oak_wall_sign",
+            ],
+        ),
+        ("slabs", &["smooth_stone_slab", "quartz_slab"]),
+        (
+            "standing_signs",
+            &[
+                "oak_sign",
+                "spruce_sign",
+                "birch_sign",
+                "jungle_sign",
+                "acacia_sign",
+                "dark_oak_sign",
+            ],
+        ),
+        ("stone_pressure_plates", &["stone_pressure_plate"]),
+        (
+            "wall_signs",
+            &[
+                "oak_wall_sign",
+                "spruce_wall_sign",
+                "birch_wall_sign",
+                "jungle_wall_sign",
+                "acacia_wall_sign",
+                "dark_oak_wall_sign",
+            ],
+        ),
+        (
+            "wool",
+            &[
+                "white_wool",
+                "orange_wool",
+                "magenta_wool",
+                "light_blue_wool",
+                "yellow_wool",
+                "lime_wool",
+                "pink_wool",
+                "gray_wool",
+                "light_gray_wool",
+                "cyan_wool",
+                "purple_wool",
+                "blue_wool",
+                "brown_wool",
+                "green_wool",
+                "red_wool",
+                "black_wool",
+            ],
+        ),
+    ];
+
+    /// All the blocks in the tag `name`, like `wool`. The `minecraft:` namespace is optional.
+    pub fn from_tag(name: &str) -> Option<Vec<Block>> {
+        let name = name.trim_start_matches("minecraft:");
+        let (_, entries) = Block::TAGS.iter().find(|(tag, _)| *tag == name)?;
+        Some(
+            entries
+                .iter()
+                .map(|entry| Block::from_name(entry).unwrap())
+                .collect(),
+        )
+    }
+}
+
+#[test]
+fn sign_block_entity_nbt_test() {
+    let sign = BlockEntity::Sign(Box::new(SignBlockEntity {
+        rows: [
+            r#"{"text":"first"}"#.to_owned(),
+            r#"{"text":"second"}"#.to_owned(),
+            r#"{"text":""}"#.to_owned(),
+            r#"{"text":"last"}"#.to_owned(),
+        ],
+    }));
+    let nbt = sign.to_nbt(BlockPos::new(12, 64, -3)).unwrap();
+    assert_eq!(nbt["id"], nbt::Value::String("minecraft:sign".to_owned()));
+    assert_eq!(
+        nbt["Text1"],
+        nbt::Value::String(r#"{"text":"first"}"#.to_owned())
+    );
+    assert_eq!(
+        nbt["Text4"],
+        nbt::Value::String(r#"{"text":"last"}"#.to_owned())
+    );
+    assert_eq!(nbt["x"], nbt::Value::Int(12));
+    assert_eq!(nbt["y"], nbt::Value::Int(64));
+    assert_eq!(nbt["z"], nbt::Value::Int(-3));
+
+    let container = BlockEntity::Container {
+        comparator_override: 0,
+    };
+    assert!(container.to_nbt(BlockPos::new(0, 0, 0)).is_none());
+}
+
+#[test]
+fn copy_properties_test() {
+    let mut repeater = Block::from_name("repeater").unwrap();
+    repeater.set_property("facing", "west");
+    repeater.set_property("delay", "3");
+    assert_eq!(repeater.property("facing"), Some("west"));
+    assert_eq!(repeater.property("delay"), Some("3"));
+    assert_eq!(repeater.property("mode"), None);
+
+    let mut comparator = Block::from_name("comparator").unwrap();
+    comparator.copy_properties(repeater);
+    assert_eq!(comparator.property("facing"), Some("west"));
+
+    // Observers can face up, which repeaters can't
+    let mut observer = Block::from_name("observer").unwrap();
+    observer.set_property("facing", "up");
+    let mut copied = repeater;
+    copied.copy_properties(observer);
+    assert_eq!(copied, repeater);
+}
+
+#[test]
+fn block_name_and_properties_test() {
+    let mut repeater = Block::from_name("repeater").unwrap();
+    repeater.set_property("facing", "west");
+    repeater.set_property("delay", "3");
+    assert_eq!(repeater.get_name(), Some("repeater"));
+    assert!(repeater.properties().contains(&("facing", "west")));
+    assert!(repeater.properties().contains(&("delay", "3")));
+
+    let red_wool = Block::from_name("red_wool").unwrap();
+    assert_eq!(red_wool.get_name(), Some("red_wool"));
+    assert!(red_wool.properties().is_empty());
+}
+
+#[test]
+fn flip_vertical_test() {
+    let block = |name: &str, properties: &[(&str, &str)]| {
+        let mut block = Block::from_name(name).unwrap();
+        for (key, val) in properties {
+            block.set_property(key, val);
+        }
+        block
+    };
+    let floor_lever = block("lever", &[("face", "floor"), ("facing", "east")]);
+    let ceiling_lever = block("lever", &[("face", "ceiling"), ("facing", "east")]);
+    assert_eq!(floor_lever.flip_vertical(), ceiling_lever);
+    assert_eq!(ceiling_lever.flip_vertical(), floor_lever);
+    let wall_lever = block("lever", &[("face", "wall"), ("facing", "east")]);
+    assert_eq!(wall_lever.flip_vertical(), wall_lever);
+
+    let ceiling_button = block("stone_button", &[("face", "ceiling")]);
+    assert_eq!(
+        ceiling_button.flip_vertical(),
+        block("stone_button", &[("face", "floor")])
+    );
+
+    let up_observer = block("observer", &[("facing", "up")]);
+    let down_observer = block("observer", &[("facing", "down")]);
+    assert_eq!(up_observer.flip_vertical(), down_observer);
+    assert_eq!(down_observer.flip_vertical(), up_observer);
+    let north_observer = block("observer", &[("facing", "north")]);
+    assert_eq!(north_observer.flip_vertical(), north_observer);
+
+    let repeater = block("repeater", &[("facing", "west")]);
+    assert_eq!(repeater.flip_vertical(), repeater);
+}
+
+#[test]
+fn block_tags_test() {
+    for (tag, _) in Block::TAGS {
+        assert!(Block::from_tag(tag).is_some());
+    }
+    let wool = Block::from_tag("minecraft:wool").unwrap();
+    assert_eq!(wool.len(), 16);
+    assert!(wool.contains(&Block::from_name("red_wool").unwrap()));
+    assert!(Block::from_tag("logs").is_none());
+}
+
+#[test]
+fn state_ids_test() {
+    let repeater = Block::from_name("repeater").unwrap();
+    assert_eq!(repeater.state_ids(), 4031..=4094);
+    let wire = Block::from_name("redstone_wire").unwrap();
+    assert_eq!(wire.state_ids(), 2058..=3353);
+    let red_wool = Block::from_name("red_wool").unwrap();
+    assert_eq!(red_wool.state_ids(), 1398..=1398);
 }
 
 #[test]
@@ -1044,7 +1552,7 @@ macro_rules! blocks {
                 },
                 from_names($name_name:ident): {
                     $(
-                        $from_name_pat:pat => {
+                        $from_name_pat:literal => {
                             $(
                                 $from_name_pkey:ident: $from_name_pval:expr
                             ),*
@@ -1088,7 +1596,7 @@ macro_rules! blocks {
                 }
             }
 
-            fn is_cube(self) -> bool {
+            pub fn is_cube(self) -> bool {
                 match self {
                     $(
                         $( Block::$name { .. } => $cube, )?
@@ -1125,6 +1633,15 @@ macro_rules! blocks {
                 }
             }
 
+            /// All the names that can be passed to `Block::from_name`
+            pub const NAMES: &'static [&'static str] = &[
+                $(
+                    $(
+                        $from_name_pat,
+                    )*
+                )*
+            ];
+
             pub fn from_name(name: &str) -> Option<Block> {
                 match name {
                     $(
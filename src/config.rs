@@ -43,7 +43,9 @@ macro_rules! gen_config {
                             $name: match config.$name {
                                 Some(entry) => entry,
                                 None => {
-                                    toml_patch += &format!("{} = {}\n", stringify!($name), default_config.$name);
+                                    // Strings have to be quoted, so the value is written as TOML
+                                    let value = toml::Value::try_from(&default_config.$name).unwrap();
+                                    toml_patch += &format!("{} = {}\n", stringify!($name), value);
                                     default_config.$name
                                 }
                             },
@@ -64,7 +66,18 @@ gen_config! {
     motd: String = "Minecraft High Performance Redstone Server".to_string(),
     chat_format: String = "<{username}> {message}".to_string(),
     max_players: i64 = 99999,
-    bungeecord: bool = false
+    bungeecord: bool = false,
+    worldedit_limit: i64 = 1000000,
+    worldedit_max_limit: i64 = 10000000,
+    worldedit_wand_item: i64 = 586,
+    worldedit_trace_distance: i64 = 300,
+    worldedit_effects: bool = false,
+    worldedit_titles: bool = false,
+    worldedit_time_budget_ms: i64 = 50,
+    worldedit_permissions: Vec<String> = vec!["*".to_string()],
+    worldedit_trusted_players: Vec<String> = Vec::new(),
+    worldedit_trusted_permissions: Vec<String> = vec!["*".to_string(), "upload".to_string()],
+    schems_dir: String = "./schems".to_string()
 }
 
 fn write_config(config: &ServerConfig) {
@@ -1,6 +1,7 @@
 use crate::blocks::{Block, BlockColorVariant, BlockDirection, BlockFace, BlockPos};
+use crate::config::CONFIG;
 use crate::network::packets::clientbound::{C2EOpenSignEditor, ClientBoundPacket};
-use crate::plot::Plot;
+use crate::plot::{worldedit, Plot};
 use crate::world::World;
 
 #[derive(PartialEq, Copy, Clone)]
@@ -42,13 +43,16 @@ impl ItemStack {
         let can_place = self.item_type.is_block() && plot.get_block(block_pos).can_place_block_in();
         let mut cancelled = false;
 
-        if let Item::WEWand {} = self.item_type {
+        if self.item_type.is_worldedit_wand()
+            && plot.players[context.player_idx].worldedit_wand_enabled
+        {
             let same = plot.players[context.player_idx]
                 .second_position
                 .map_or(false, |p| p == use_pos);
             if !same {
                 plot.players[context.player_idx]
                     .worldedit_set_second_position(use_pos.x, use_pos.y, use_pos.z);
+                worldedit::broadcast_selection(plot, context.player_idx);
             }
             cancelled = true;
         }
@@ -368,6 +372,11 @@ items! {
 }
 
 impl Item {
+    /// Returns true if this item is the one configured to be used as the worldedit wand
+    pub fn is_worldedit_wand(self) -> bool {
+        self.get_id() == CONFIG.worldedit_wand_item as u32
+    }
+
     pub fn from_name(name: &str) -> Option<Item> {
         match name {
             "snowball" => Some(Item::Snowball {}),
@@ -1,6 +1,6 @@
 pub mod packets;
 
-use packets::{read_packet, serverbound::ServerBoundPacket, PacketEncoder};
+use packets::{read_packet, serverbound::ServerBoundPacket, PacketBatch, PacketEncoder};
 use std::io::Write;
 use std::net::{Shutdown, TcpListener, TcpStream};
 use std::sync::{
@@ -9,6 +9,15 @@ use std::sync::{
 };
 use std::thread;
 
+/// The protocol version of Minecraft 1.16.4, the only version clients can join with.
+///
+/// Supporting more versions would mean encoding packets based on the protocol version of each
+/// client. 1.17 for example changed the chunk packet: `C20ChunkData` lost its full chunk flag,
+/// the primary bit mask became a bit set and the sections are laid out differently. A packet like
+/// that would get a separate struct for each format, and the code sending it would pick one by
+/// matching on `NetworkClient::protocol_version`.
+pub const PROTOCOL_VERSION: i32 = 754;
+
 /// The minecraft protocol has these 4 different states.
 #[derive(PartialEq, Clone)]
 pub enum NetworkState {
@@ -25,6 +34,8 @@ pub struct NetworkClient {
     pub id: u32,
     stream: TcpStream,
     pub state: NetworkState,
+    /// The protocol version the client sent in its handshake.
+    pub protocol_version: i32,
     packets: mpsc::Receiver<Box<dyn ServerBoundPacket>>,
     pub alive: bool,
     compressed: Arc<AtomicBool>,
@@ -33,6 +44,33 @@ pub struct NetworkClient {
 }
 
 impl NetworkClient {
+    /// Creates a client connected over the loopback interface for tests. The returned thread
+    /// collects everything that is sent to the client until it is dropped.
+    #[cfg(test)]
+    pub fn test_client() -> (NetworkClient, thread::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut peer = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let received = thread::spawn(move || {
+            let mut data = Vec::new();
+            let _ = std::io::Read::read_to_end(&mut peer, &mut data);
+            data
+        });
+        let (_, packets) = mpsc::channel();
+        let client = NetworkClient {
+            id: 0,
+            stream,
+            state: NetworkState::Play,
+            protocol_version: PROTOCOL_VERSION,
+            packets,
+            alive: true,
+            compressed: Arc::new(AtomicBool::new(false)),
+            username: None,
+            uuid: None,
+        };
+        (client, received)
+    }
+
     fn listen(
         mut stream: TcpStream,
         sender: mpsc::Sender<Box<dyn ServerBoundPacket>>,
@@ -79,6 +117,15 @@ impl NetworkClient {
         }
     }
 
+    /// Sends all the packets in the batch with a single write.
+    pub fn send_batch(&mut self, batch: &mut PacketBatch) {
+        if self.compressed.load(Ordering::Relaxed) {
+            let _ = self.stream.write_all(batch.compressed());
+        } else {
+            let _ = self.stream.write_all(batch.uncompressed());
+        }
+    }
+
     pub fn close_connection(&mut self) {
         self.alive = false;
         let _ = self.stream.shutdown(Shutdown::Both);
@@ -111,6 +158,7 @@ impl NetworkServer {
                     id: index as u32,
                     stream,
                     state: NetworkState::Handshake,
+                    protocol_version: 0,
                     packets: packet_receiver,
                     alive: true,
                     compressed,
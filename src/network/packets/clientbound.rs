@@ -183,6 +183,26 @@ impl ClientBoundPacket for C05EntityAnimation {
     }
 }
 
+pub struct C07AcknowledgePlayerDigging {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub block: i32,
+    pub status: i32,
+    pub successful: bool,
+}
+
+impl ClientBoundPacket for C07AcknowledgePlayerDigging {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_position(self.x, self.y, self.z);
+        buf.write_varint(self.block);
+        buf.write_varint(self.status);
+        buf.write_boolean(self.successful);
+        PacketEncoder::new(buf, 0x07)
+    }
+}
+
 pub struct C09BlockEntityData {
     pub x: i32,
     pub y: i32,
@@ -217,6 +237,57 @@ impl ClientBoundPacket for C0BBlockChange {
     }
 }
 
+pub enum C0CBossBarAction {
+    Add {
+        title: String,
+        health: f32,
+        color: i32,
+        division: i32,
+        flags: u8,
+    },
+    Remove,
+    UpdateHealth(f32),
+    UpdateTitle(String),
+}
+
+pub struct C0CBossBar {
+    pub uuid: u128,
+    pub action: C0CBossBarAction,
+}
+
+impl ClientBoundPacket for C0CBossBar {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_uuid(self.uuid);
+        match self.action {
+            C0CBossBarAction::Add {
+                title,
+                health,
+                color,
+                division,
+                flags,
+            } => {
+                buf.write_varint(0);
+                buf.write_string(32767, &title);
+                buf.write_float(health);
+                buf.write_varint(color);
+                buf.write_varint(division);
+                buf.write_unsigned_byte(flags);
+            }
+            C0CBossBarAction::Remove => buf.write_varint(1),
+            C0CBossBarAction::UpdateHealth(health) => {
+                buf.write_varint(2);
+                buf.write_float(health);
+            }
+            C0CBossBarAction::UpdateTitle(title) => {
+                buf.write_varint(3);
+                buf.write_string(32767, &title);
+            }
+        }
+        PacketEncoder::new(buf, 0x0C)
+    }
+}
+
 pub struct C0EChatMessage {
     pub message: String,
     pub position: i8,
@@ -233,6 +304,37 @@ impl ClientBoundPacket for C0EChatMessage {
     }
 }
 
+pub struct C0FTabCompleteMatch {
+    pub text: String,
+    /// The tooltip as a json chat component
+    pub tooltip: Option<String>,
+}
+
+pub struct C0FTabComplete {
+    pub transaction_id: i32,
+    pub start: i32,
+    pub length: i32,
+    pub matches: Vec<C0FTabCompleteMatch>,
+}
+
+impl ClientBoundPacket for C0FTabComplete {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_varint(self.transaction_id);
+        buf.write_varint(self.start);
+        buf.write_varint(self.length);
+        buf.write_varint(self.matches.len() as i32);
+        for tab_match in self.matches {
+            buf.write_string(32767, &tab_match.text);
+            buf.write_bool(tab_match.tooltip.is_some());
+            if let Some(tooltip) = tab_match.tooltip {
+                buf.write_string(32767, &tooltip);
+            }
+        }
+        PacketEncoder::new(buf, 0x0F)
+    }
+}
+
 pub enum C10DeclareCommandsNodeParser {
     Entity(i8),
     Vec3,
@@ -240,6 +342,8 @@ pub enum C10DeclareCommandsNodeParser {
     Float(f32, f32),
     BlockPos,
     BlockState,
+    /// 0 for a single word, 1 for a quotable phrase and 2 for a greedy phrase
+    String(i32),
 }
 
 impl C10DeclareCommandsNodeParser {
@@ -253,6 +357,10 @@ impl C10DeclareCommandsNodeParser {
             Vec3 => buf.write_string(32767, "minecraft:vec3"),
             BlockPos => buf.write_string(32767, "minecraft:block_pos"),
             BlockState => buf.write_string(32767, "minecraft:block_state"),
+            String(mode) => {
+                buf.write_string(32767, "brigadier:string");
+                buf.write_varint(*mode);
+            }
             Integer(min, max) => {
                 buf.write_string(32767, "brigadier:integer");
                 buf.write_byte(3); // Supply min and max value
@@ -275,6 +383,7 @@ pub struct C10DeclareCommandsNode {
     pub redirect_node: Option<i32>,
     pub name: Option<&'static str>,
     pub parser: Option<C10DeclareCommandsNodeParser>,
+    pub suggestions_type: Option<&'static str>,
 }
 
 pub struct C10DeclareCommands {
@@ -301,6 +410,9 @@ impl ClientBoundPacket for C10DeclareCommands {
             if let Some(parser) = node.parser {
                 parser.write(&mut buf);
             }
+            if let Some(suggestions_type) = node.suggestions_type {
+                buf.write_string(32767, suggestions_type);
+            }
         }
         buf.write_varint(self.root_index);
         PacketEncoder::new(buf, 0x10)
@@ -318,23 +430,29 @@ impl ClientBoundPacket for C13WindowItems {
         buf.write_unsigned_byte(self.window_id);
         buf.write_short(self.slot_data.len() as i16);
         for slot_data in self.slot_data {
-            if let Some(slot) = slot_data {
-                buf.write_bool(true);
-                buf.write_varint(slot.item_id);
-                buf.write_byte(slot.item_count);
-                if let Some(nbt) = slot.nbt {
-                    buf.write_nbt_blob(nbt);
-                } else {
-                    buf.write_byte(0); // End tag
-                }
-            } else {
-                buf.write_bool(false);
-            }
+            buf.write_slot(&slot_data);
         }
         PacketEncoder::new(buf, 0x13)
     }
 }
 
+pub struct C15SetSlot {
+    /// The window id is -1 for the cursor and 0 for the player inventory
+    pub window_id: i8,
+    pub slot: i16,
+    pub slot_data: Option<SlotData>,
+}
+
+impl ClientBoundPacket for C15SetSlot {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_byte(self.window_id);
+        buf.write_short(self.slot);
+        buf.write_slot(&self.slot_data);
+        PacketEncoder::new(buf, 0x15)
+    }
+}
+
 pub struct C17PluginMessage {
     pub channel: String,
     pub data: Vec<u8>,
@@ -349,6 +467,31 @@ impl ClientBoundPacket for C17PluginMessage {
     }
 }
 
+pub struct C18NamedSoundEffect {
+    pub sound_name: String,
+    pub sound_category: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub volume: f32,
+    pub pitch: f32,
+}
+
+impl ClientBoundPacket for C18NamedSoundEffect {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_string(32767, &self.sound_name);
+        buf.write_varint(self.sound_category);
+        // The position is sent as fixed-point numbers with 3 fraction bits
+        buf.write_int((self.x * 8.0) as i32);
+        buf.write_int((self.y * 8.0) as i32);
+        buf.write_int((self.z * 8.0) as i32);
+        buf.write_float(self.volume);
+        buf.write_float(self.pitch);
+        PacketEncoder::new(buf, 0x18)
+    }
+}
+
 pub struct C19Disconnect {
     pub reason: String,
 }
@@ -361,7 +504,59 @@ impl ClientBoundPacket for C19Disconnect {
     }
 }
 
+pub struct C1AEntityStatus {
+    pub entity_id: i32,
+    pub status: i8,
+}
+
+impl ClientBoundPacket for C1AEntityStatus {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_int(self.entity_id);
+        buf.write_byte(self.status);
+        PacketEncoder::new(buf, 0x1A)
+    }
+}
+
 #[derive(Debug)]
+pub struct C1BExplosionRecord {
+    pub x: i8,
+    pub y: i8,
+    pub z: i8,
+}
+
+pub struct C1BExplosion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub strength: f32,
+    /// The offsets of the destroyed blocks from the center of the explosion
+    pub records: Vec<C1BExplosionRecord>,
+    pub player_motion_x: f32,
+    pub player_motion_y: f32,
+    pub player_motion_z: f32,
+}
+
+impl ClientBoundPacket for C1BExplosion {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_float(self.x);
+        buf.write_float(self.y);
+        buf.write_float(self.z);
+        buf.write_float(self.strength);
+        buf.write_int(self.records.len() as i32);
+        for record in self.records {
+            buf.write_byte(record.x);
+            buf.write_byte(record.y);
+            buf.write_byte(record.z);
+        }
+        buf.write_float(self.player_motion_x);
+        buf.write_float(self.player_motion_y);
+        buf.write_float(self.player_motion_z);
+        PacketEncoder::new(buf, 0x1B)
+    }
+}
+
 pub struct C1CUnloadChunk {
     pub chunk_x: i32,
     pub chunk_z: i32,
@@ -488,6 +683,100 @@ impl ClientBoundPacket for C21Effect {
     }
 }
 
+pub enum C22ParticleData {
+    None,
+    /// Used by the `block` and `falling_dust` particles
+    Block(i32),
+    Dust {
+        red: f32,
+        green: f32,
+        blue: f32,
+        scale: f32,
+    },
+}
+
+pub struct C22Particle {
+    pub particle_id: i32,
+    pub long_distance: bool,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub offset_z: f32,
+    pub particle_data: f32,
+    pub particle_count: i32,
+    pub data: C22ParticleData,
+}
+
+impl ClientBoundPacket for C22Particle {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_int(self.particle_id);
+        buf.write_bool(self.long_distance);
+        buf.write_double(self.x);
+        buf.write_double(self.y);
+        buf.write_double(self.z);
+        buf.write_float(self.offset_x);
+        buf.write_float(self.offset_y);
+        buf.write_float(self.offset_z);
+        buf.write_float(self.particle_data);
+        buf.write_int(self.particle_count);
+        match self.data {
+            C22ParticleData::None => {}
+            C22ParticleData::Block(block_state) => buf.write_varint(block_state),
+            C22ParticleData::Dust {
+                red,
+                green,
+                blue,
+                scale,
+            } => {
+                buf.write_float(red);
+                buf.write_float(green);
+                buf.write_float(blue);
+                buf.write_float(scale);
+            }
+        }
+        PacketEncoder::new(buf, 0x22)
+    }
+}
+
+pub struct C23UpdateLight {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub trust_edges: bool,
+    pub sky_light_mask: i32,
+    pub block_light_mask: i32,
+    pub empty_sky_light_mask: i32,
+    pub empty_block_light_mask: i32,
+    /// One array of 2048 bytes for every bit set in `sky_light_mask`
+    pub sky_light_arrays: Vec<Vec<u8>>,
+    /// One array of 2048 bytes for every bit set in `block_light_mask`
+    pub block_light_arrays: Vec<Vec<u8>>,
+}
+
+impl ClientBoundPacket for C23UpdateLight {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_varint(self.chunk_x);
+        buf.write_varint(self.chunk_z);
+        buf.write_bool(self.trust_edges);
+        buf.write_varint(self.sky_light_mask);
+        buf.write_varint(self.block_light_mask);
+        buf.write_varint(self.empty_sky_light_mask);
+        buf.write_varint(self.empty_block_light_mask);
+        for array in self.sky_light_arrays {
+            buf.write_varint(array.len() as i32);
+            buf.write_bytes(array);
+        }
+        for array in self.block_light_arrays {
+            buf.write_varint(array.len() as i32);
+            buf.write_bytes(array);
+        }
+        PacketEncoder::new(buf, 0x23)
+    }
+}
+
 #[derive(Serialize)]
 pub struct C24JoinGameDimensionElement {
     pub natural: i8,
@@ -536,7 +825,8 @@ pub struct C24JoinGameBiomeElement {
 
 pub struct C24JoinGameDimensionCodec {
     pub dimensions: HashMap<String, C24JoinGameDimensionElement>,
-    pub biomes: HashMap<String, C24JoinGameBiomeElement>,
+    /// The biomes are given ids in this order
+    pub biomes: Vec<(String, C24JoinGameBiomeElement)>,
 }
 
 #[derive(Serialize)]
@@ -609,6 +899,23 @@ impl ClientBoundPacket for C24JoinGame {
     }
 }
 
+pub struct C2DOpenWindow {
+    pub window_id: i32,
+    pub window_type: i32,
+    /// The window title as a json chat component
+    pub window_title: String,
+}
+
+impl ClientBoundPacket for C2DOpenWindow {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_varint(self.window_id);
+        buf.write_varint(self.window_type);
+        buf.write_string(32767, &self.window_title);
+        PacketEncoder::new(buf, 0x2D)
+    }
+}
+
 pub struct C2EOpenSignEditor {
     pub pos_x: i32,
     pub pos_y: i32,
@@ -779,6 +1086,42 @@ impl ClientBoundPacket for C32PlayerInfo {
     }
 }
 
+#[derive(Clone, Copy)]
+pub enum C33FacePlayerAnchor {
+    Feet = 0,
+    Eyes = 1,
+}
+
+pub struct C33FacePlayerEntity {
+    pub entity_id: i32,
+    pub entity_anchor: C33FacePlayerAnchor,
+}
+
+pub struct C33FacePlayer {
+    pub anchor: C33FacePlayerAnchor,
+    pub target_x: f64,
+    pub target_y: f64,
+    pub target_z: f64,
+    /// If set, the client faces this entity instead of the target position
+    pub entity: Option<C33FacePlayerEntity>,
+}
+
+impl ClientBoundPacket for C33FacePlayer {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_varint(self.anchor as i32);
+        buf.write_double(self.target_x);
+        buf.write_double(self.target_y);
+        buf.write_double(self.target_z);
+        buf.write_bool(self.entity.is_some());
+        if let Some(entity) = self.entity {
+            buf.write_varint(entity.entity_id);
+            buf.write_varint(entity.entity_anchor as i32);
+        }
+        PacketEncoder::new(buf, 0x33)
+    }
+}
+
 pub struct C34PlayerPositionAndLook {
     pub x: f64,
     pub y: f64,
@@ -818,6 +1161,33 @@ impl ClientBoundPacket for C36DestroyEntities {
     }
 }
 
+pub struct C39Respawn {
+    pub dimension: C24JoinGameDimensionElement,
+    pub world_name: String,
+    pub hashed_seed: i64,
+    pub gamemode: u8,
+    pub previous_gamemode: u8,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    /// Whether the client should keep the entity metadata of the player
+    pub copy_metadata: bool,
+}
+
+impl ClientBoundPacket for C39Respawn {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_nbt(self.dimension);
+        buf.write_string(32767, &self.world_name);
+        buf.write_long(self.hashed_seed);
+        buf.write_unsigned_byte(self.gamemode);
+        buf.write_unsigned_byte(self.previous_gamemode);
+        buf.write_boolean(self.is_debug);
+        buf.write_boolean(self.is_flat);
+        buf.write_boolean(self.copy_metadata);
+        PacketEncoder::new(buf, 0x39)
+    }
+}
+
 pub struct C3AEntityHeadLook {
     pub entity_id: i32,
     pub yaw: f32,
@@ -869,6 +1239,20 @@ impl ClientBoundPacket for C3BMultiBlockChange {
     }
 }
 
+/// Makes the client view the world from the perspective of another entity. Sending the
+/// player's own entity id switches the view back.
+pub struct C3ECamera {
+    pub camera_id: i32,
+}
+
+impl ClientBoundPacket for C3ECamera {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_varint(self.camera_id);
+        PacketEncoder::new(buf, 0x3E)
+    }
+}
+
 pub struct C3FHeldItemChange {
     pub slot: i8,
 }
@@ -895,6 +1279,35 @@ impl ClientBoundPacket for C40UpdateViewPosition {
     }
 }
 
+pub struct C42SpawnPosition {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl ClientBoundPacket for C42SpawnPosition {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_position(self.x, self.y, self.z);
+        PacketEncoder::new(buf, 0x42)
+    }
+}
+
+pub struct C43DisplayScoreboard {
+    /// 0: list, 1: sidebar, 2: below name, 3-18: team specific sidebar
+    pub position: i8,
+    pub score_name: String,
+}
+
+impl ClientBoundPacket for C43DisplayScoreboard {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_byte(self.position);
+        buf.write_string(16, &self.score_name);
+        PacketEncoder::new(buf, 0x43)
+    }
+}
+
 pub struct C44EntityMetadataEntry {
     pub index: u8,
     pub metadata_type: i32,
@@ -920,6 +1333,24 @@ impl ClientBoundPacket for C44EntityMetadata {
     }
 }
 
+pub struct C46EntityVelocity {
+    pub entity_id: i32,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
+
+impl ClientBoundPacket for C46EntityVelocity {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_varint(self.entity_id);
+        buf.write_short(self.velocity_x);
+        buf.write_short(self.velocity_y);
+        buf.write_short(self.velocity_z);
+        PacketEncoder::new(buf, 0x46)
+    }
+}
+
 pub struct C47EntityEquipmentEquipment {
     pub slot: i32,
     pub item: Option<SlotData>,
@@ -936,24 +1367,121 @@ impl ClientBoundPacket for C47EntityEquipment {
         buf.write_varint(self.entity_id);
         for slot in self.equipment {
             buf.write_varint(slot.slot);
-            if let Some(slot) = slot.item {
-                buf.write_bool(true);
-                buf.write_varint(slot.item_id);
-                buf.write_byte(slot.item_count);
-                if let Some(nbt) = slot.nbt {
-                    buf.write_nbt_blob(nbt);
-                } else {
-                    buf.write_byte(0); // End tag
-                }
-            } else {
-                buf.write_bool(false);
-            }
+            buf.write_slot(&slot.item);
         }
 
         PacketEncoder::new(buf, 0x47)
     }
 }
 
+pub struct C48SetExperience {
+    /// Between 0 and 1
+    pub experience_bar: f32,
+    pub level: i32,
+    pub total_experience: i32,
+}
+
+impl ClientBoundPacket for C48SetExperience {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_float(self.experience_bar);
+        buf.write_varint(self.level);
+        buf.write_varint(self.total_experience);
+        PacketEncoder::new(buf, 0x48)
+    }
+}
+
+pub struct C49UpdateHealth {
+    pub health: f32,
+    pub food: i32,
+    pub food_saturation: f32,
+}
+
+impl ClientBoundPacket for C49UpdateHealth {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_float(self.health);
+        buf.write_varint(self.food);
+        buf.write_float(self.food_saturation);
+        PacketEncoder::new(buf, 0x49)
+    }
+}
+
+pub enum C4AScoreboardObjectiveMode {
+    Create {
+        display_name: String,
+        /// 0: integer, 1: hearts
+        render_type: i32,
+    },
+    Remove,
+    UpdateText {
+        display_name: String,
+        render_type: i32,
+    },
+}
+
+pub struct C4AScoreboardObjective {
+    pub objective_name: String,
+    pub mode: C4AScoreboardObjectiveMode,
+}
+
+impl ClientBoundPacket for C4AScoreboardObjective {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_string(16, &self.objective_name);
+        match self.mode {
+            C4AScoreboardObjectiveMode::Create {
+                display_name,
+                render_type,
+            } => {
+                buf.write_byte(0);
+                buf.write_string(32767, &display_name);
+                buf.write_varint(render_type);
+            }
+            C4AScoreboardObjectiveMode::Remove => buf.write_byte(1),
+            C4AScoreboardObjectiveMode::UpdateText {
+                display_name,
+                render_type,
+            } => {
+                buf.write_byte(2);
+                buf.write_string(32767, &display_name);
+                buf.write_varint(render_type);
+            }
+        }
+        PacketEncoder::new(buf, 0x4A)
+    }
+}
+
+pub enum C4DUpdateScoreAction {
+    Update(i32),
+    Remove,
+}
+
+pub struct C4DUpdateScore {
+    pub entity_name: String,
+    pub objective_name: String,
+    pub action: C4DUpdateScoreAction,
+}
+
+impl ClientBoundPacket for C4DUpdateScore {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_string(40, &self.entity_name);
+        match self.action {
+            C4DUpdateScoreAction::Update(value) => {
+                buf.write_byte(0);
+                buf.write_string(16, &self.objective_name);
+                buf.write_varint(value);
+            }
+            C4DUpdateScoreAction::Remove => {
+                buf.write_byte(1);
+                buf.write_string(16, &self.objective_name);
+            }
+        }
+        PacketEncoder::new(buf, 0x4D)
+    }
+}
+
 pub struct C4ETimeUpdate {
     pub world_age: i64,
     pub time_of_day: i64,
@@ -968,6 +1496,82 @@ impl ClientBoundPacket for C4ETimeUpdate {
     }
 }
 
+pub enum C4FTitle {
+    SetTitle(String),
+    SetSubtitle(String),
+    SetActionBar(String),
+    SetTimesAndDisplay {
+        fade_in: i32,
+        stay: i32,
+        fade_out: i32,
+    },
+    Hide,
+    Reset,
+}
+
+impl ClientBoundPacket for C4FTitle {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        match self {
+            C4FTitle::SetTitle(text) => {
+                buf.write_varint(0);
+                buf.write_string(32767, &text);
+            }
+            C4FTitle::SetSubtitle(text) => {
+                buf.write_varint(1);
+                buf.write_string(32767, &text);
+            }
+            C4FTitle::SetActionBar(text) => {
+                buf.write_varint(2);
+                buf.write_string(32767, &text);
+            }
+            C4FTitle::SetTimesAndDisplay {
+                fade_in,
+                stay,
+                fade_out,
+            } => {
+                buf.write_varint(3);
+                buf.write_int(fade_in);
+                buf.write_int(stay);
+                buf.write_int(fade_out);
+            }
+            C4FTitle::Hide => buf.write_varint(4),
+            C4FTitle::Reset => buf.write_varint(5),
+        }
+        PacketEncoder::new(buf, 0x4F)
+    }
+}
+
+pub struct C53PlayerListHeaderAndFooter {
+    pub header: String,
+    pub footer: String,
+}
+
+impl ClientBoundPacket for C53PlayerListHeaderAndFooter {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_string(32767, &self.header);
+        buf.write_string(32767, &self.footer);
+        PacketEncoder::new(buf, 0x53)
+    }
+}
+
+pub struct C55CollectItem {
+    pub collected_entity_id: i32,
+    pub collector_entity_id: i32,
+    pub pickup_item_count: i32,
+}
+
+impl ClientBoundPacket for C55CollectItem {
+    fn encode(self) -> PacketEncoder {
+        let mut buf = Vec::new();
+        buf.write_varint(self.collected_entity_id);
+        buf.write_varint(self.collector_entity_id);
+        buf.write_varint(self.pickup_item_count);
+        PacketEncoder::new(buf, 0x55)
+    }
+}
+
 pub struct C56EntityTeleport {
     pub entity_id: i32,
     pub x: f64,
@@ -991,3 +1595,12 @@ impl ClientBoundPacket for C56EntityTeleport {
         PacketEncoder::new(buf, 0x56)
     }
 }
+
+#[test]
+fn entity_status() {
+    let packet = C1AEntityStatus {
+        entity_id: 300,
+        status: 2,
+    };
+    assert_eq!(packet.encode().uncompressed(), [6, 0x1A, 0, 0, 1, 0x2C, 2]);
+}
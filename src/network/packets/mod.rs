@@ -29,6 +29,7 @@ pub enum PacketDecodeError {
     IoError(io::Error),
     FromUtf8Error(std::string::FromUtf8Error),
     NbtError(nbt::Error),
+    VarIntTooBig,
 }
 
 impl From<nbt::Error> for PacketDecodeError {
@@ -93,6 +94,7 @@ fn read_decompressed<T: PacketDecoderExt>(
         _ => match packet_id {
             0x03 => Box::new(S03ChatMessage::decode(reader)?),
             0x05 => Box::new(S05ClientSettings::decode(reader)?),
+            0x06 => Box::new(S06TabComplete::decode(reader)?),
             0x0B => Box::new(S0BPluginMessage::decode(reader)?),
             0x10 => Box::new(S10KeepAlive::decode(reader)?),
             0x12 => Box::new(S12PlayerPosition::decode(reader)?),
@@ -129,6 +131,7 @@ pub fn read_packet<T: PacketDecoderExt>(
 
 impl<T: std::convert::AsRef<[u8]>> PacketDecoderExt for Cursor<T> {}
 impl PacketDecoderExt for TcpStream {}
+impl PacketDecoderExt for &[u8] {}
 
 pub trait PacketDecoderExt: Read + Sized {
     fn read_unsigned_byte(&mut self) -> DecodeResult<u8> {
@@ -184,7 +187,7 @@ pub trait PacketDecoderExt: Read + Sized {
 
             num_read += 1;
             if num_read > 5 {
-                panic!("VarInt is too big!");
+                return Err(PacketDecodeError::VarIntTooBig);
             }
             if read & 0b1000_0000 == 0 {
                 break;
@@ -203,8 +206,8 @@ pub trait PacketDecoderExt: Read + Sized {
             result |= value << (7 * num_read);
 
             num_read += 1;
-            if num_read > 5 {
-                panic!("VarInt is too big!");
+            if num_read > 10 {
+                return Err(PacketDecodeError::VarIntTooBig);
             }
             if read & 0b1000_0000 == 0 {
                 break;
@@ -325,6 +328,8 @@ pub trait PacketEncoderExt: Write {
 
     fn write_nbt_blob(&mut self, blob: nbt::Blob);
 
+    fn write_slot(&mut self, slot: &Option<SlotData>);
+
     fn write_nbt<T: Serialize>(&mut self, nbt: T) {
         let _ = nbt::to_writer(self, &nbt, None);
     }
@@ -334,6 +339,21 @@ impl PacketEncoderExt for Vec<u8> {
     fn write_nbt_blob(&mut self, blob: nbt::Blob) {
         blob.to_writer(self).unwrap();
     }
+
+    fn write_slot(&mut self, slot: &Option<SlotData>) {
+        if let Some(slot) = slot {
+            self.write_bool(true);
+            self.write_varint(slot.item_id);
+            self.write_byte(slot.item_count);
+            if let Some(nbt) = &slot.nbt {
+                nbt.to_writer(self).unwrap();
+            } else {
+                self.write_byte(0); // End tag
+            }
+        } else {
+            self.write_bool(false);
+        }
+    }
 }
 
 pub struct PacketEncoder {
@@ -364,28 +384,131 @@ impl PacketEncoder {
     }
 
     pub fn compressed(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        self.write_compressed(&mut out, &mut encoder);
+        out
+    }
+
+    /// Appends the compressed packet to `out`. The `encoder` is reset afterwards so that it
+    /// can be reused for the next packet.
+    fn write_compressed(&self, out: &mut Vec<u8>, encoder: &mut ZlibEncoder<Vec<u8>>) {
         let packet_id = PacketEncoder::varint(self.packet_id as i32);
         let data = [&packet_id[..], &self.buffer[..]].concat();
         if self.buffer.len() < 256 {
             let data_length = PacketEncoder::varint(0);
             let packet_length = PacketEncoder::varint((data_length.len() + data.len()) as i32);
-            [&packet_length[..], &data_length[..], &data[..]].concat()
+            out.extend_from_slice(&packet_length);
+            out.extend_from_slice(&data_length);
+            out.extend_from_slice(&data);
         } else {
             let data_length = PacketEncoder::varint(data.len() as i32);
-            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
             encoder.write_all(&data).unwrap();
-            let compressed = encoder.finish().unwrap();
+            let compressed = encoder.reset(Vec::new()).unwrap();
             let packet_length =
                 PacketEncoder::varint((data_length.len() + compressed.len()) as i32);
-
-            [&packet_length[..], &data_length[..], &compressed[..]].concat()
+            out.extend_from_slice(&packet_length);
+            out.extend_from_slice(&data_length);
+            out.extend_from_slice(&compressed);
         }
     }
 
     pub fn uncompressed(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_uncompressed(&mut out);
+        out
+    }
+
+    fn write_uncompressed(&self, out: &mut Vec<u8>) {
         let packet_id = PacketEncoder::varint(self.packet_id as i32);
         let length = PacketEncoder::varint((self.buffer.len() + packet_id.len()) as i32);
+        out.extend_from_slice(&length);
+        out.extend_from_slice(&packet_id);
+        out.extend_from_slice(&self.buffer);
+    }
+}
 
-        [&length[..], &packet_id[..], &self.buffer[..]].concat()
+/// Packets that are sent together with a single write. Each packet only gets compressed once,
+/// no matter how many clients the batch is sent to.
+#[derive(Default)]
+pub struct PacketBatch {
+    packets: Vec<PacketEncoder>,
+    compressed: Option<Vec<u8>>,
+    uncompressed: Option<Vec<u8>>,
+}
+
+impl PacketBatch {
+    pub fn new() -> PacketBatch {
+        Default::default()
+    }
+
+    pub fn push(&mut self, packet: PacketEncoder) {
+        self.packets.push(packet);
+        self.compressed = None;
+        self.uncompressed = None;
+    }
+
+    pub fn compressed(&mut self) -> &[u8] {
+        let packets = &self.packets;
+        self.compressed.get_or_insert_with(|| {
+            let mut out = Vec::new();
+            // The same encoder is used for all the packets so its buffers are reused
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            for packet in packets {
+                packet.write_compressed(&mut out, &mut encoder);
+            }
+            out
+        })
     }
+
+    pub fn uncompressed(&mut self) -> &[u8] {
+        let packets = &self.packets;
+        self.uncompressed.get_or_insert_with(|| {
+            let mut out = Vec::new();
+            for packet in packets {
+                packet.write_uncompressed(&mut out);
+            }
+            out
+        })
+    }
+}
+
+#[test]
+fn write_slot() {
+    let mut buf = Vec::new();
+    buf.write_slot(&None);
+    assert_eq!(buf, [0]);
+
+    let mut buf = Vec::new();
+    buf.write_slot(&Some(SlotData {
+        item_id: 586,
+        item_count: 64,
+        nbt: None,
+    }));
+    assert_eq!(buf, [1, 0xCA, 0x04, 64, 0]);
+}
+
+#[test]
+fn packet_batch_matches_single_packets() {
+    let small = || PacketEncoder::new(vec![1, 2, 3], 0x10);
+    let large = || PacketEncoder::new((0..1000).map(|i| (i % 7) as u8).collect(), 0x20);
+
+    let mut batch = PacketBatch::new();
+    batch.push(large());
+    batch.push(small());
+    batch.push(large());
+    let compressed = [
+        large().compressed(),
+        small().compressed(),
+        large().compressed(),
+    ]
+    .concat();
+    let uncompressed = [
+        large().uncompressed(),
+        small().uncompressed(),
+        large().uncompressed(),
+    ]
+    .concat();
+    assert_eq!(batch.compressed(), &compressed[..]);
+    assert_eq!(batch.uncompressed(), &uncompressed[..]);
 }
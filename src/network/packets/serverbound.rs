@@ -7,6 +7,7 @@ pub trait ServerBoundPacketHandler {
     fn handle_login_start(&mut self, _packet: S00LoginStart, _player_idx: usize) {}
     fn handle_chat_message(&mut self, _packet: S03ChatMessage, _player_idx: usize) {}
     fn handle_client_settings(&mut self, _packet: S05ClientSettings, _player_idx: usize) {}
+    fn handle_tab_complete(&mut self, _packet: S06TabComplete, _player_idx: usize) {}
     fn handle_plugin_message(&mut self, _packet: S0BPluginMessage, _player_idx: usize) {}
     fn handle_keep_alive(&mut self, _packet: S10KeepAlive, _player_idx: usize) {}
     fn handle_player_position(&mut self, _packet: S12PlayerPosition, _player_idx: usize) {}
@@ -167,6 +168,24 @@ impl ServerBoundPacket for S05ClientSettings {
     }
 }
 
+pub struct S06TabComplete {
+    pub transaction_id: i32,
+    pub text: String,
+}
+
+impl ServerBoundPacket for S06TabComplete {
+    fn decode<T: PacketDecoderExt>(decoder: &mut T) -> DecodeResult<Self> {
+        Ok(S06TabComplete {
+            transaction_id: decoder.read_varint()?,
+            text: decoder.read_string()?,
+        })
+    }
+
+    fn handle(self: Box<Self>, handler: &mut dyn ServerBoundPacketHandler, player_idx: usize) {
+        handler.handle_tab_complete(*self, player_idx);
+    }
+}
+
 pub struct S0BPluginMessage {
     pub channel: String,
     pub data: Vec<u8>,
@@ -1,9 +1,11 @@
 use crate::blocks::{BlockDirection, BlockFacing, BlockPos};
 use crate::chat::ChatComponent;
+use crate::config::CONFIG;
 use crate::items::{Item, ItemStack};
 use crate::network::packets::clientbound::*;
+use crate::network::packets::SlotData;
 use crate::network::NetworkClient;
-use crate::plot::worldedit::{WorldEditClipboard, WorldEditUndo};
+use crate::plot::worldedit::{ClipboardUpload, SelectionMode, WorldEditClipboard, WorldEditUndo};
 use byteorder::{BigEndian, ReadBytesExt};
 use log::warn;
 use serde::{Deserialize, Serialize};
@@ -11,6 +13,7 @@ use serde_json::json;
 use std::fmt;
 use std::fs::{self, OpenOptions};
 use std::io::{Cursor, Write};
+use std::sync::Arc;
 use std::time::{Instant, SystemTime};
 
 /// This is a single item in the player's inventory
@@ -103,8 +106,20 @@ pub struct Player {
     pub first_position: Option<BlockPos>,
     /// The worldedit second position.
     pub second_position: Option<BlockPos>,
+    /// Whether the worldedit wand sets positions when it's used. This is toggled with //toggleeditwand
+    pub worldedit_wand_enabled: bool,
+    /// The maximum number of blocks a worldedit operation can affect. This is set with //limit
+    pub worldedit_limit: usize,
+    /// How the worldedit positions make up the selection. This is set with //sel
+    pub worldedit_selection_mode: SelectionMode,
+    /// Whether the selections of other players on the plot are sent to this player's CUI
+    pub worldedit_show_selections: bool,
+    /// Whether the client announced WorldEditCUI support. CUI messages are only sent if it did.
+    pub worldedit_cui_enabled: bool,
     /// The worldedit current clipboard.
-    pub worldedit_clipboard: Option<WorldEditClipboard>,
+    pub worldedit_clipboard: Option<Arc<WorldEditClipboard>>,
+    /// The schematic the client is uploading to the clipboard, see `worldedit::CLIPBOARD_CHANNEL`
+    pub worldedit_clipboard_upload: ClipboardUpload,
     /// The saved sections used for worldedit //undo
     /// Each entry stores the plot coords and the clipboard
     pub worldedit_undo: Vec<WorldEditUndo>,
@@ -191,7 +206,13 @@ impl Player {
                 last_keep_alive_sent: Instant::now(),
                 first_position: None,
                 second_position: None,
+                worldedit_wand_enabled: true,
+                worldedit_limit: CONFIG.worldedit_limit as usize,
+                worldedit_selection_mode: SelectionMode::Cuboid,
+                worldedit_show_selections: false,
+                worldedit_cui_enabled: false,
                 worldedit_clipboard: None,
+                worldedit_clipboard_upload: ClipboardUpload::default(),
                 worldedit_undo: Vec::new(),
                 command_queue: Vec::new(),
             }
@@ -229,7 +250,13 @@ impl Player {
             last_keep_alive_sent: Instant::now(),
             first_position: None,
             second_position: None,
+            worldedit_wand_enabled: true,
+            worldedit_limit: CONFIG.worldedit_limit as usize,
+            worldedit_selection_mode: SelectionMode::Cuboid,
+            worldedit_show_selections: false,
+            worldedit_cui_enabled: false,
             worldedit_clipboard: None,
+            worldedit_clipboard_upload: ClipboardUpload::default(),
             worldedit_undo: Vec::new(),
             command_queue: Vec::new(),
         }
@@ -408,19 +435,149 @@ impl Player {
         );
     }
 
+    /// Sets the text above and below the player list (`header` and `footer` are not in json format)
+    pub fn send_player_list_header_and_footer(&mut self, header: &str, footer: &str) {
+        let packet = C53PlayerListHeaderAndFooter {
+            header: json!({ "text": header }).to_string(),
+            footer: json!({ "text": footer, "color": "gray" }).to_string(),
+        }
+        .encode();
+        self.client.send_packet(&packet);
+    }
+
+    /// Shows a light purple title and subtitle in the middle of the player's screen
+    /// (`title` and `subtitle` are not in json format)
+    pub fn send_worldedit_title(&mut self, title: &str, subtitle: &str) {
+        let subtitle = C4FTitle::SetSubtitle(
+            json!({
+                "text": subtitle,
+                "color": "light_purple"
+            })
+            .to_string(),
+        )
+        .encode();
+        self.client.send_packet(&subtitle);
+        let title = C4FTitle::SetTitle(
+            json!({
+                "text": title,
+                "color": "light_purple"
+            })
+            .to_string(),
+        )
+        .encode();
+        self.client.send_packet(&title);
+    }
+
+    /// Plays a sound at the player's position which only they can hear
+    pub fn play_sound(&mut self, sound_name: &str, volume: f32, pitch: f32) {
+        let sound = C18NamedSoundEffect {
+            sound_name: sound_name.to_owned(),
+            // The "master" category
+            sound_category: 0,
+            x: self.x,
+            y: self.y,
+            z: self.z,
+            volume,
+            pitch,
+        }
+        .encode();
+        self.client.send_packet(&sound);
+    }
+
     pub fn worldedit_set_first_position(&mut self, x: i32, y: i32, z: i32) {
-        self.send_worldedit_message(&format!("First position set to ({}, {}, {})", x, y, z));
         self.first_position = Some(BlockPos::new(x, y, z));
-        self.worldedit_send_cui(&format!("p|0|{}|{}|{}|0", x, y, z));
+        if self.worldedit_selection_mode == SelectionMode::Extend {
+            // A new extended selection starts out as just the first position
+            self.second_position = self.first_position;
+        }
+        self.send_worldedit_message(&format!(
+            "First position set to ({}, {}, {}){}",
+            x,
+            y,
+            z,
+            self.worldedit_selection_volume_suffix()
+        ));
+        self.worldedit_send_selection_cui();
     }
 
     pub fn worldedit_set_second_position(&mut self, x: i32, y: i32, z: i32) {
-        self.send_worldedit_message(&format!("Second position set to ({}, {}, {})", x, y, z));
-        self.second_position = Some(BlockPos::new(x, y, z));
-        self.worldedit_send_cui(&format!("p|1|{}|{}|{}|0", x, y, z));
+        let pos = BlockPos::new(x, y, z);
+        if let (SelectionMode::Extend, Some(first_pos), Some(second_pos)) = (
+            self.worldedit_selection_mode,
+            self.first_position,
+            self.second_position,
+        ) {
+            let min = first_pos.min(second_pos).min(pos);
+            let max = first_pos.max(second_pos).max(pos);
+            self.first_position = Some(min);
+            self.second_position = Some(max);
+            self.send_worldedit_message(&format!(
+                "Selection extended to ({}, {}, {}) - ({}, {}, {}){}",
+                min.x,
+                min.y,
+                min.z,
+                max.x,
+                max.y,
+                max.z,
+                self.worldedit_selection_volume_suffix()
+            ));
+            self.worldedit_send_selection_cui();
+            return;
+        }
+        self.second_position = Some(pos);
+        self.send_worldedit_message(&format!(
+            "Second position set to ({}, {}, {}){}",
+            x,
+            y,
+            z,
+            self.worldedit_selection_volume_suffix()
+        ));
+        self.worldedit_send_selection_cui();
+    }
+
+    /// The amount of blocks in the selection, or `None` while only one of the positions is set.
+    pub fn worldedit_selection_volume(&self) -> Option<i64> {
+        let (first_pos, second_pos) = (self.first_position?, self.second_position?);
+        let size = first_pos.max(second_pos) - first_pos.min(second_pos);
+        Some((size.x as i64 + 1) * (size.y as i64 + 1) * (size.z as i64 + 1))
+    }
+
+    /// The size of the selection to put after a position in chat, like ` (64 blocks)`.
+    fn worldedit_selection_volume_suffix(&self) -> String {
+        match self.worldedit_selection_volume() {
+            Some(volume) => format!(" ({} blocks)", volume),
+            None => String::new(),
+        }
+    }
+
+    /// The WorldEditCUI messages for the points of the selection. Each point is sent as
+    /// `p|<index>|<x>|<y>|<z>|<area>`, where the index is 0 for the first position and 1 for
+    /// the second one. The area is the amount of blocks in the selection, or -1 while only
+    /// one of the positions is set.
+    pub fn worldedit_selection_cui_points(&self) -> Vec<String> {
+        let area = self.worldedit_selection_volume().unwrap_or(-1);
+        [self.first_position, self.second_position]
+            .iter()
+            .enumerate()
+            .filter_map(|(i, pos)| {
+                pos.map(|pos| format!("p|{}|{}|{}|{}|{}", i, pos.x, pos.y, pos.z, area))
+            })
+            .collect()
+    }
+
+    /// Sends the whole selection to WorldEditCUI. `s|cuboid` starts a new cuboid selection,
+    /// which also clears the old one, and it is followed by the points that are set.
+    pub fn worldedit_send_selection_cui(&mut self) {
+        self.worldedit_send_cui("s|cuboid");
+        for message in self.worldedit_selection_cui_points() {
+            self.worldedit_send_cui(&message);
+        }
     }
 
     pub fn worldedit_send_cui(&mut self, message: &str) {
+        if !self.worldedit_cui_enabled {
+            return;
+        }
         let cui_plugin_message = C17PluginMessage {
             channel: String::from("worldedit:cui"),
             data: Vec::from(message.as_bytes()),
@@ -429,6 +586,27 @@ impl Player {
         self.client.send_packet(&cui_plugin_message);
     }
 
+    /// Sends the player's entire inventory to the client
+    pub fn send_inventory(&mut self) {
+        let slot_data: Vec<Option<SlotData>> = self
+            .inventory
+            .iter()
+            .map(|op| {
+                op.as_ref().map(|item| SlotData {
+                    item_count: item.count as i8,
+                    item_id: item.item_type.get_id() as i32,
+                    nbt: item.nbt.clone(),
+                })
+            })
+            .collect();
+        let window_items = C13WindowItems {
+            window_id: 0,
+            slot_data,
+        }
+        .encode();
+        self.client.send_packet(&window_items);
+    }
+
     /// Sends the player the disconnect packet, it is still up to the player to end the network stream.
     pub fn kick(&mut self, reason: String) {
         let disconnect = C19Disconnect { reason }.encode();
@@ -222,6 +222,7 @@ bitflags! {
         const ARGUMENT = 0x2;
         const EXECUTABLE = 0x4;
         const REDIRECT = 0x8;
+        const HAS_SUGGESTIONS_TYPE = 0x10;
     }
 }
 
@@ -231,7 +232,7 @@ lazy_static! {
     /// The DeclareCommands packet that is sent when the player joins.
     /// This is used for command autocomplete.
     pub static ref DECLARE_COMMANDS: PacketEncoder = C10DeclareCommands {
-        nodes: vec![
+        nodes: with_worldedit_commands(vec![
             // 0: Root Node
             Node {
                 flags: CommandFlags::ROOT.bits() as i8,
@@ -239,6 +240,7 @@ lazy_static! {
                 redirect_node: None,
                 name: None,
                 parser: None,
+                suggestions_type: None,
             },
             // 1: /teleport
             Node {
@@ -247,6 +249,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("teleport"),
                 parser: None,
+                suggestions_type: None,
             },
             // 2: /teleport [x, y, z]
             Node {
@@ -255,6 +258,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("x, y, z"),
                 parser: Some(Parser::Vec3),
+                suggestions_type: None,
             },
             // 3: /teleport [player]
             Node {
@@ -263,6 +267,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("player"),
                 parser: Some(Parser::Entity(3)), // Only allow one player
+                suggestions_type: None,
             },
             // 4: /tp
             Node {
@@ -271,6 +276,7 @@ lazy_static! {
                 redirect_node: Some(1),
                 name: Some("tp"),
                 parser: None,
+                suggestions_type: None,
             },
             // 5: /stop
             Node {
@@ -279,6 +285,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("stop"),
                 parser: None,
+                suggestions_type: None,
             },
             // 6: /plot
             Node {
@@ -287,6 +294,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("plot"),
                 parser: None,
+                suggestions_type: None,
             },
             // 7: /plot info
             Node {
@@ -295,6 +303,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("info"),
                 parser: None,
+                suggestions_type: None,
             },
             // 8: /plot i
             Node {
@@ -303,6 +312,7 @@ lazy_static! {
                 redirect_node: Some(7),
                 name: Some("i"),
                 parser: None,
+                suggestions_type: None,
             },
             // 9: /plot claim
             Node {
@@ -311,6 +321,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("claim"),
                 parser: None,
+                suggestions_type: None,
             },
             // 10: /plot c
             Node {
@@ -319,6 +330,7 @@ lazy_static! {
                 redirect_node: Some(9),
                 name: Some("c"),
                 parser: None,
+                suggestions_type: None,
             },
             // 11: /p
             Node {
@@ -327,6 +339,7 @@ lazy_static! {
                 redirect_node: Some(6),
                 name: Some("p"),
                 parser: None,
+                suggestions_type: None,
             },
             // 12: /rtps
             Node {
@@ -335,6 +348,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("rtps"),
                 parser: None,
+                suggestions_type: None,
             },
             // 13: /rtps [rtps]
             Node {
@@ -343,6 +357,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("rtps"),
                 parser: Some(Parser::Integer(0, 35000)),
+                suggestions_type: None,
             },
             // 14: //pos1
             Node {
@@ -351,6 +366,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("/pos1"),
                 parser: None,
+                suggestions_type: None,
             },
             // 15: //pos1 [pos]
             Node {
@@ -359,6 +375,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("pos"),
                 parser: Some(Parser::BlockPos),
+                suggestions_type: None,
             },
             // 16: //pos2
             Node {
@@ -367,6 +384,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("/pos2"),
                 parser: None,
+                suggestions_type: None,
             },
             // 17: //pos2 [pos]
             Node {
@@ -375,6 +393,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("pos"),
                 parser: Some(Parser::BlockPos),
+                suggestions_type: None,
             },
             // 18: /1
             Node {
@@ -383,6 +402,7 @@ lazy_static! {
                 redirect_node: Some(14),
                 name: Some("/1"),
                 parser: None,
+                suggestions_type: None,
             },
             // 19: /2
             Node {
@@ -391,6 +411,7 @@ lazy_static! {
                 redirect_node: Some(16),
                 name: Some("/2"),
                 parser: None,
+                suggestions_type: None,
             },
             // 20: //copy
             Node {
@@ -399,6 +420,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("/copy"),
                 parser: None,
+                suggestions_type: None,
             },
             // 21: //c
             Node {
@@ -407,6 +429,7 @@ lazy_static! {
                 redirect_node: Some(20),
                 name: Some("/c"),
                 parser: None,
+                suggestions_type: None,
             },
             // 22: //paste
             Node {
@@ -415,6 +438,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("/paste"),
                 parser: None,
+                suggestions_type: None,
             },
             // 23: //p
             Node {
@@ -423,6 +447,7 @@ lazy_static! {
                 redirect_node: Some(20),
                 name: Some("/p"),
                 parser: None,
+                suggestions_type: None,
             },
             // 24: //set
             Node {
@@ -431,14 +456,16 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("/set"),
                 parser: None,
+                suggestions_type: None,
             },
-            // 25: //set [block]
+            // 25: //set [pattern]
             Node {
-                flags: (CommandFlags::ARGUMENT | CommandFlags::EXECUTABLE).bits() as i8,
+                flags: (CommandFlags::ARGUMENT | CommandFlags::EXECUTABLE | CommandFlags::HAS_SUGGESTIONS_TYPE).bits() as i8,
                 children: vec![],
                 redirect_node: None,
-                name: Some("block"),
-                parser: Some(Parser::BlockState),
+                name: Some("pattern"),
+                parser: Some(Parser::String(2)),
+                suggestions_type: Some("minecraft:ask_server"),
             },
             // 26: //replace
             Node {
@@ -447,6 +474,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("/replace"),
                 parser: None,
+                suggestions_type: None,
             },
             // 27: //replace [oldblock]
             Node {
@@ -455,6 +483,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("oldblock"),
                 parser: Some(Parser::BlockState),
+                suggestions_type: None,
             },
             // 28: //replace [oldblock] [newblock]
             Node {
@@ -463,6 +492,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("newblock"),
                 parser: Some(Parser::BlockState),
+                suggestions_type: None,
             },
             // 29: /radvance
             Node {
@@ -471,6 +501,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("radvance"),
                 parser: None,
+                suggestions_type: None,
             },
             // 30: /radvance [rticks]
             Node {
@@ -479,6 +510,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("rticks"),
                 parser: Some(Parser::Integer(0, 35000)),
+                suggestions_type: None,
             },
             // 31: /radv
             Node {
@@ -487,6 +519,7 @@ lazy_static! {
                 redirect_node: Some(29),
                 name: Some("radv"),
                 parser: None,
+                suggestions_type: None,
             },
             // 32: /speed
             Node {
@@ -495,6 +528,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("speed"),
                 parser: None,
+                suggestions_type: None,
             },
             // 33: /speed [speed]
             Node {
@@ -503,6 +537,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("speed"),
                 parser: Some(Parser::Float(0.0, 10.0)),
+                suggestions_type: None,
             },
             // 34: //stack
             Node {
@@ -511,6 +546,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("/stack"),
                 parser: None,
+                suggestions_type: None,
             },
             // 35: //stack [amount]
             Node {
@@ -519,6 +555,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("amount"),
                 parser: Some(Parser::Integer(0, 256)),
+                suggestions_type: None,
             },
             // 36: //undo
             Node {
@@ -527,6 +564,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("/undo"),
                 parser: None,
+                suggestions_type: None,
             },
             // 37: //sel
             Node {
@@ -535,6 +573,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("/sel"),
                 parser: None,
+                suggestions_type: None,
             },
             // 38: /p auto
             Node {
@@ -543,6 +582,7 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("auto"),
                 parser: None,
+                suggestions_type: None,
             },
             // 39: /p a
             Node {
@@ -551,6 +591,7 @@ lazy_static! {
                 redirect_node: Some(9),
                 name: Some("a"),
                 parser: None,
+                suggestions_type: None,
             },
             // 40: /p auto
             Node {
@@ -559,8 +600,48 @@ lazy_static! {
                 redirect_node: None,
                 name: Some("middle"),
                 parser: None,
+                suggestions_type: None,
             },
-        ],
+        ]),
         root_index: 0
     }.encode();
 }
+
+/// Declares the worldedit commands which aren't already in `nodes` with a single argument
+/// that the server completes.
+fn with_worldedit_commands(mut nodes: Vec<Node>) -> Vec<Node> {
+    for name in worldedit::command_names() {
+        // The literal for `//name` is `/name`. The names are only created once so leaking
+        // them is fine.
+        let literal: &'static str = Box::leak(format!("/{}", name).into_boxed_str());
+        let declared = nodes[0]
+            .children
+            .iter()
+            .any(|&child| nodes[child as usize].name == Some(literal));
+        if declared {
+            continue;
+        }
+        let idx = nodes.len() as i32;
+        nodes[0].children.push(idx);
+        nodes.push(Node {
+            flags: (CommandFlags::LITERAL | CommandFlags::EXECUTABLE).bits() as i8,
+            children: vec![idx + 1],
+            redirect_node: None,
+            name: Some(literal),
+            parser: None,
+            suggestions_type: None,
+        });
+        nodes.push(Node {
+            flags: (CommandFlags::ARGUMENT
+                | CommandFlags::EXECUTABLE
+                | CommandFlags::HAS_SUGGESTIONS_TYPE)
+                .bits() as i8,
+            children: vec![],
+            redirect_node: None,
+            name: Some("arguments"),
+            parser: Some(Parser::String(2)),
+            suggestions_type: Some("minecraft:ask_server"),
+        });
+    }
+    nodes
+}
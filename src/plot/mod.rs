@@ -5,6 +5,7 @@ pub mod worldedit;
 
 use crate::blocks::{Block, BlockEntity, BlockPos};
 use crate::chat::ChatComponent;
+use crate::config::CONFIG;
 use crate::network::packets::clientbound::*;
 use crate::network::packets::SlotData;
 use crate::player::{Gamemode, Player};
@@ -16,6 +17,7 @@ use log::warn;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
 use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::Path;
@@ -23,6 +25,9 @@ use std::sync::mpsc::{Receiver, Sender};
 use std::thread;
 use std::time::{Duration, SystemTime};
 
+/// The number of layers of ground that new plots are generated with
+pub const GENERATED_LAYERS: i32 = 8;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PlotData {
     pub tps: u32,
@@ -42,6 +47,7 @@ pub struct Plot {
     last_update_time: SystemTime,
     lag_time: Duration,
     last_player_time: SystemTime,
+    last_player_list_update: SystemTime,
     sleep_time: Duration,
     running: bool,
     x: i32,
@@ -49,6 +55,10 @@ pub struct Plot {
     show_redstone: bool,
     always_running: bool,
     chunks: Vec<Chunk>,
+    /// Large worldedit operations which are processed a bit at a time on every update
+    worldedit_queue: VecDeque<worldedit::WorldEditJob>,
+    /// Named copies of regions made with `//snapshot save`. They aren't saved with the plot.
+    worldedit_snapshots: HashMap<String, worldedit::WorldEditClipboard>,
 }
 
 impl World for Plot {
@@ -57,7 +67,7 @@ impl World for Plot {
         let chunk_index = self.get_chunk_index_for_block(pos.x, pos.z);
 
         // Check to see if block is within height limit
-        if chunk_index >= 256 || pos.y > 256 {
+        if chunk_index >= 256 || !(0..256).contains(&pos.y) {
             return false;
         }
 
@@ -78,7 +88,7 @@ impl World for Plot {
         let chunk_index = self.get_chunk_index_for_block(pos.x, pos.z);
 
         // Check to see if block is within height limit
-        if chunk_index >= 256 || pos.y > 256 {
+        if chunk_index >= 256 || !(0..256).contains(&pos.y) {
             return false;
         }
 
@@ -128,20 +138,7 @@ impl World for Plot {
         if chunk_index >= 256 {
             return;
         }
-        if let Some(nbt) = block_entity.to_nbt(pos) {
-            let block_entity_data = C09BlockEntityData {
-                x: pos.x,
-                y: pos.y,
-                z: pos.z,
-                // For now the only nbt we send to the client is sign data
-                action: 9,
-                nbt,
-            }
-            .encode();
-            for player in &mut self.players {
-                player.client.send_packet(&block_entity_data);
-            }
-        }
+        self.send_block_entity_data(pos, &block_entity);
         let chunk = &mut self.chunks[chunk_index];
         chunk.set_block_entity(BlockPos::new(pos.x & 0xF, pos.y, pos.z & 0xF), block_entity);
     }
@@ -207,6 +204,25 @@ impl Plot {
         }
     }
 
+    /// Send the client side data of a block entity to all connected players.
+    /// Block entities without any data for the client are skipped.
+    pub fn send_block_entity_data(&mut self, pos: BlockPos, block_entity: &BlockEntity) {
+        if let Some(nbt) = block_entity.to_nbt(pos) {
+            let block_entity_data = C09BlockEntityData {
+                x: pos.x,
+                y: pos.y,
+                z: pos.z,
+                // For now the only nbt we send to the client is sign data
+                action: 9,
+                nbt,
+            }
+            .encode();
+            for player in &mut self.players {
+                player.client.send_packet(&block_entity_data);
+            }
+        }
+    }
+
     pub fn broadcast_chat_message(&mut self, message: String) {
         let broadcast_message =
             Message::ChatInfo(0, format!("Plot {}-{}", self.x, self.z), message);
@@ -405,6 +421,7 @@ impl Plot {
     }
 
     fn leave_plot(&mut self, player_index: usize) -> Player {
+        worldedit::finish_jobs_of(self, self.players[player_index].uuid);
         let mut player = self.players.remove(player_index);
         let mut entity_ids = Vec::new();
         for player in &self.players {
@@ -438,6 +455,19 @@ impl Plot {
         x >= plot_x * 256 && x < (plot_x + 1) * 256 && z >= plot_z * 256 && z < (plot_z + 1) * 256
     }
 
+    fn update_player_list_header_and_footer(&mut self) {
+        let footer = format!(
+            "Plot: {}, {} | Players: {} | RTPS: {}",
+            self.x,
+            self.z,
+            self.players.len(),
+            self.tps
+        );
+        for player in &mut self.players {
+            player.send_player_list_header_and_footer(&CONFIG.motd, &footer);
+        }
+    }
+
     pub fn claim_plot(&mut self, plot_x: i32, plot_z: i32, player: usize) {
         database::claim_plot(
             plot_x,
@@ -618,6 +648,11 @@ impl Plot {
                 self.running = false;
             }
         }
+        // Refresh the player list info every second
+        if self.last_player_list_update.elapsed().unwrap_or_default() >= Duration::from_secs(1) {
+            self.last_player_list_update = SystemTime::now();
+            self.update_player_list_header_and_footer();
+        }
         // Update players
         for player_idx in 0..self.players.len() {
             if self.players[player_idx].update() {
@@ -631,9 +666,19 @@ impl Plot {
 
         self.handle_commands();
 
-        let message_sender = &mut self.message_sender;
+        worldedit::process_queue(self);
 
         // Remove disconnected players
+        let disconnected_uuids: Vec<u128> = self
+            .players
+            .iter()
+            .filter(|player| !player.client.alive)
+            .map(|player| player.uuid)
+            .collect();
+        for uuid in disconnected_uuids {
+            worldedit::finish_jobs_of(self, uuid);
+        }
+        let message_sender = &mut self.message_sender;
         let mut disconnected_players = Vec::new();
         self.players.retain(|player| {
             let alive = player.client.alive;
@@ -680,22 +725,33 @@ impl Plot {
     ) -> Plot {
         let chunk_x_offset = x << 4;
         let chunk_z_offset = z << 4;
-        let plot_data: PlotData = bincode::deserialize(&data).unwrap();
+        let mut reader = &data[..];
+        let plot_data: PlotData = bincode::deserialize_from(&mut reader).unwrap();
+        // Plots saved before biomes existed end right after the plot data
+        let biomes: Vec<[u8; 16]> = bincode::deserialize_from(&mut reader)
+            .ok()
+            .filter(|biomes: &Vec<[u8; 16]>| biomes.len() == plot_data.chunk_data.len())
+            .unwrap_or_default();
         let chunks: Vec<Chunk> = plot_data
             .chunk_data
             .into_iter()
             .enumerate()
             .map(|(i, c)| {
-                Chunk::load(
+                let mut chunk = Chunk::load(
                     chunk_x_offset + i as i32 / 16,
                     chunk_z_offset + i as i32 % 16,
                     c,
-                )
+                );
+                if let Some(biomes) = biomes.get(i) {
+                    chunk.biomes = *biomes;
+                }
+                chunk
             })
             .collect();
         Plot {
             last_player_time: SystemTime::now(),
             last_update_time: SystemTime::now(),
+            last_player_list_update: SystemTime::UNIX_EPOCH,
             lag_time: Duration::new(0, 0),
             sleep_time: Duration::from_micros(
                 1_000_000u64
@@ -713,6 +769,8 @@ impl Plot {
             z,
             always_running,
             chunks,
+            worldedit_queue: VecDeque::new(),
+            worldedit_snapshots: HashMap::new(),
             to_be_ticked: plot_data.pending_ticks,
         }
     }
@@ -731,53 +789,73 @@ impl Plot {
             let data = fs::read("./world/plots/pTEMPLATE").unwrap();
             Plot::load_from_file(data, x, z, rx, tx, priv_rx, always_running)
         } else {
-            let chunk_x_offset = x << 4;
-            let chunk_z_offset = z << 4;
-            let mut chunks = Vec::new();
-            for chunk_x in 0..16 {
-                for chunk_z in 0..16 {
-                    chunks.push(Chunk::generate(
-                        8,
-                        chunk_x + chunk_x_offset,
-                        chunk_z + chunk_z_offset,
-                    ));
-                }
-            }
-            Plot {
-                last_player_time: SystemTime::now(),
-                last_update_time: SystemTime::now(),
-                lag_time: Duration::new(0, 0),
-                sleep_time: Duration::from_millis(50),
-                message_receiver: rx,
-                message_sender: tx,
-                priv_message_receiver: priv_rx,
-                players: Vec::new(),
-                running: true,
-                show_redstone: true,
-                tps: 10,
-                x,
-                z,
-                always_running,
-                chunks,
-                to_be_ticked: Vec::new(),
+            Plot::generate(x, z, rx, tx, priv_rx, always_running)
+        }
+    }
+
+    /// Creates a new plot with the default ground
+    fn generate(
+        x: i32,
+        z: i32,
+        rx: BusReader<BroadcastMessage>,
+        tx: Sender<Message>,
+        priv_rx: Receiver<PrivMessage>,
+        always_running: bool,
+    ) -> Plot {
+        let chunk_x_offset = x << 4;
+        let chunk_z_offset = z << 4;
+        let mut chunks = Vec::new();
+        for chunk_x in 0..16 {
+            for chunk_z in 0..16 {
+                chunks.push(Chunk::generate(
+                    GENERATED_LAYERS,
+                    chunk_x + chunk_x_offset,
+                    chunk_z + chunk_z_offset,
+                ));
             }
         }
+        Plot {
+            last_player_time: SystemTime::now(),
+            last_update_time: SystemTime::now(),
+            last_player_list_update: SystemTime::UNIX_EPOCH,
+            lag_time: Duration::new(0, 0),
+            sleep_time: Duration::from_millis(50),
+            message_receiver: rx,
+            message_sender: tx,
+            priv_message_receiver: priv_rx,
+            players: Vec::new(),
+            running: true,
+            show_redstone: true,
+            tps: 10,
+            x,
+            z,
+            always_running,
+            chunks,
+            worldedit_queue: VecDeque::new(),
+            worldedit_snapshots: HashMap::new(),
+            to_be_ticked: Vec::new(),
+        }
     }
 
     fn save(&self) {
         let mut file = OpenOptions::new()
             .write(true)
             .create(true)
+            .truncate(true)
             .open(format!("./world/plots/p{},{}", self.x, self.z))
             .unwrap();
         let chunk_data: Vec<ChunkData> = self.chunks.iter().map(|c| c.save()).collect();
-        let encoded: Vec<u8> = bincode::serialize(&PlotData {
+        let mut encoded: Vec<u8> = bincode::serialize(&PlotData {
             tps: self.tps,
             show_redstone: self.show_redstone,
             chunk_data,
             pending_ticks: self.to_be_ticked.clone(),
         })
         .unwrap();
+        // The biomes are appended after the plot data so that plots saved without them can
+        // still be loaded
+        let biomes: Vec<[u8; 16]> = self.chunks.iter().map(|c| c.biomes).collect();
+        encoded.extend(bincode::serialize(&biomes).unwrap());
         file.write_all(&encoded).unwrap();
         file.sync_data().unwrap();
     }
@@ -833,6 +911,10 @@ impl Drop for Plot {
                     .unwrap();
             }
         }
+        // A job that made the plot crash would only crash it again
+        if !thread::panicking() {
+            worldedit::finish_queue(self);
+        }
         self.save();
         self.message_sender
             .send(Message::PlotUnload(self.x, self.z))
@@ -840,6 +922,43 @@ impl Drop for Plot {
     }
 }
 
+/// A generated plot at 0, 0 with a player standing in the middle of it. The returned thread
+/// collects everything that is sent to the player, see `test_plot_packets`.
+#[cfg(test)]
+fn test_plot() -> (Plot, thread::JoinHandle<Vec<u8>>) {
+    let (tx, _) = std::sync::mpsc::channel();
+    let (_, priv_rx) = std::sync::mpsc::channel();
+    let mut bus = bus::Bus::new(1);
+    let mut plot = Plot::generate(0, 0, bus.add_rx(), tx, priv_rx, false);
+    let (client, received) = crate::network::NetworkClient::test_client();
+    plot.players
+        .push(Player::load_player(0, "Tester".to_owned(), client));
+    (plot, received)
+}
+
+/// Disconnects the players of a plot made by `test_plot` and returns the ids and data of the
+/// packets that were sent to them.
+#[cfg(test)]
+fn test_plot_packets(mut plot: Plot, received: thread::JoinHandle<Vec<u8>>) -> Vec<(i32, Vec<u8>)> {
+    use crate::network::packets::PacketDecoderExt;
+    for player in &mut plot.players {
+        player.client.close_connection();
+    }
+    // Dropping the plot would save it and its players to disk
+    std::mem::forget(plot);
+    let data = received.join().unwrap();
+    let mut data = &data[..];
+    let mut packets = Vec::new();
+    while !data.is_empty() {
+        let len = data.read_varint().unwrap() as usize;
+        let (mut packet, rest) = data.split_at(len);
+        data = rest;
+        let id = packet.read_varint().unwrap();
+        packets.push((id, packet.to_vec()));
+    }
+    packets
+}
+
 #[test]
 fn chunk_save_and_load_test() {
     let mut chunk = Chunk::empty(1, 1);
@@ -1,4 +1,4 @@
-use super::Plot;
+use super::{worldedit, Plot, GENERATED_LAYERS};
 use crate::blocks::{BlockEntity, BlockFace, BlockPos, SignBlockEntity};
 use crate::items::{Item, ItemStack, UseOnBlockContext};
 use crate::network::packets::clientbound::*;
@@ -6,6 +6,7 @@ use crate::network::packets::serverbound::*;
 use crate::network::packets::SlotData;
 use crate::player::SkinParts;
 use crate::server::Message;
+use crate::world::storage::Chunk;
 use crate::world::World;
 use serde_json::json;
 use std::time::Instant;
@@ -17,6 +18,27 @@ impl Plot {
             packet.handle(self, player);
         }
     }
+
+    /// Lets the client know whether it was allowed to dig at the position of `digging` and
+    /// which block is there now, so that it doesn't show a block that isn't there.
+    fn acknowledge_digging(
+        &mut self,
+        player: usize,
+        digging: &S1BPlayerDigging,
+        block_id: u32,
+        successful: bool,
+    ) {
+        let acknowledge = C07AcknowledgePlayerDigging {
+            x: digging.x,
+            y: digging.y,
+            z: digging.z,
+            block: block_id as i32,
+            status: digging.status,
+            successful,
+        }
+        .encode();
+        self.players[player].client.send_packet(&acknowledge);
+    }
 }
 
 impl ServerBoundPacketHandler for Plot {
@@ -173,9 +195,34 @@ impl ServerBoundPacketHandler for Plot {
         }
     }
 
+    fn handle_tab_complete(&mut self, tab_complete: S06TabComplete, player: usize) {
+        let text = tab_complete.text;
+        if let Some((start, matches)) = worldedit::complete_command(&self.players[player], &text) {
+            let matches = matches
+                .into_iter()
+                .map(|(text, tooltip)| C0FTabCompleteMatch {
+                    text,
+                    tooltip: tooltip.map(|tooltip| json!({ "text": tooltip }).to_string()),
+                })
+                .collect();
+            let tab_complete = C0FTabComplete {
+                transaction_id: tab_complete.transaction_id,
+                start: start as i32,
+                length: (text.len() - start) as i32,
+                matches,
+            }
+            .encode();
+            self.players[player].client.send_packet(&tab_complete);
+        }
+    }
+
     fn handle_plugin_message(&mut self, plugin_message: S0BPluginMessage, player: usize) {
         if plugin_message.channel == "worldedit:cui" {
-            self.players[player].worldedit_send_cui("s|cuboid");
+            // WorldEditCUI sends its handshake on this channel once it has loaded
+            self.players[player].worldedit_cui_enabled = true;
+            self.players[player].worldedit_send_selection_cui();
+        } else if plugin_message.channel == worldedit::CLIPBOARD_CHANNEL {
+            worldedit::receive_clipboard_part(&mut self.players[player], &plugin_message.data);
         }
     }
 
@@ -337,6 +384,11 @@ impl ServerBoundPacketHandler for Plot {
 
             if !Plot::in_plot_bounds(self.x, self.z, block_pos.x, block_pos.z) {
                 self.players[player].send_system_message("Can't break blocks outside of plot");
+                // Tell the client that the block is still there. The block belongs to another
+                // plot, so the best guess is the generated terrain.
+                let block_id =
+                    Chunk::generated_block(GENERATED_LAYERS, block_pos.x, block_pos.y, block_pos.z);
+                self.acknowledge_digging(player, &player_digging, block_id, false);
                 return;
             }
 
@@ -345,7 +397,8 @@ impl ServerBoundPacketHandler for Plot {
                 [self.players[player].selected_slot as usize + 36]
                 .clone();
             if let Some(item) = item_in_hand {
-                if item.item_type == (Item::WEWand {}) {
+                if item.item_type.is_worldedit_wand() && self.players[player].worldedit_wand_enabled
+                {
                     let block = self.get_block(block_pos);
                     self.send_block_change(block_pos, block.get_id());
                     if let Some(pos) = self.players[player].first_position {
@@ -358,12 +411,15 @@ impl ServerBoundPacketHandler for Plot {
                         block_pos.y,
                         block_pos.z,
                     );
+                    worldedit::broadcast_selection(self, player);
                     return;
                 }
             }
 
             let other_block = self.get_block(block_pos);
             other_block.destroy(self, block_pos);
+            let block = self.get_block(block_pos);
+            self.acknowledge_digging(player, &player_digging, block.get_id(), true);
 
             let effect = C21Effect {
                 effect_id: 2001,
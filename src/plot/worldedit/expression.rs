@@ -0,0 +1,238 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ExpressionParseError {
+    UnexpectedCharacter(char),
+    UnexpectedEnd,
+    InvalidNumber(String),
+    UnknownVariable(String),
+}
+
+impl fmt::Display for ExpressionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpressionParseError::UnexpectedCharacter(c) => {
+                write!(f, "unexpected character in expression: {}", c)
+            }
+            ExpressionParseError::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            ExpressionParseError::InvalidNumber(num) => write!(f, "invalid number: {}", num),
+            ExpressionParseError::UnknownVariable(var) => {
+                write!(f, "unknown variable: {} (only x, y and z can be used)", var)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExpressionParseError {}
+
+pub type ExpressionParseResult<T> = Result<T, ExpressionParseError>;
+
+#[derive(Debug, Clone, Copy)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Less,
+    Greater,
+}
+
+#[derive(Debug)]
+pub enum Expression {
+    Number(f64),
+    X,
+    Y,
+    Z,
+    Negate(Box<Expression>),
+    Binary(BinaryOp, Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// Parses an expression using `+ - * / ^`, the comparisons `<` and `>`, parentheses and
+    /// the variables `x`, `y` and `z`.
+    pub fn parse(expression: &str) -> ExpressionParseResult<Expression> {
+        let mut parser = Parser {
+            chars: expression.chars().filter(|c| !c.is_whitespace()).collect(),
+            pos: 0,
+        };
+        let expression = parser.parse_comparison()?;
+        match parser.peek() {
+            Some(c) => Err(ExpressionParseError::UnexpectedCharacter(c)),
+            None => Ok(expression),
+        }
+    }
+
+    /// Evaluates the expression. Comparisons result in 1 if they are true and 0 otherwise.
+    pub fn eval(&self, x: f64, y: f64, z: f64) -> f64 {
+        match self {
+            Expression::Number(num) => *num,
+            Expression::X => x,
+            Expression::Y => y,
+            Expression::Z => z,
+            Expression::Negate(expr) => -expr.eval(x, y, z),
+            Expression::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval(x, y, z);
+                let rhs = rhs.eval(x, y, z);
+                match op {
+                    BinaryOp::Add => lhs + rhs,
+                    BinaryOp::Sub => lhs - rhs,
+                    BinaryOp::Mul => lhs * rhs,
+                    BinaryOp::Div => lhs / rhs,
+                    BinaryOp::Pow => lhs.powf(rhs),
+                    BinaryOp::Less => (lhs < rhs) as u8 as f64,
+                    BinaryOp::Greater => (lhs > rhs) as u8 as f64,
+                }
+            }
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> ExpressionParseResult<char> {
+        let c = self.peek().ok_or(ExpressionParseError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn binary(op: BinaryOp, lhs: Expression, rhs: Expression) -> Expression {
+        Expression::Binary(op, Box::new(lhs), Box::new(rhs))
+    }
+
+    fn parse_comparison(&mut self) -> ExpressionParseResult<Expression> {
+        let lhs = self.parse_additive()?;
+        let op = match self.peek() {
+            Some('<') => BinaryOp::Less,
+            Some('>') => BinaryOp::Greater,
+            _ => return Ok(lhs),
+        };
+        self.pos += 1;
+        let rhs = self.parse_additive()?;
+        Ok(Parser::binary(op, lhs, rhs))
+    }
+
+    fn parse_additive(&mut self) -> ExpressionParseResult<Expression> {
+        let mut lhs = self.parse_term()?;
+        loop {
+            let op = match self.peek() {
+                Some('+') => BinaryOp::Add,
+                Some('-') => BinaryOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.parse_term()?;
+            lhs = Parser::binary(op, lhs, rhs);
+        }
+    }
+
+    fn parse_term(&mut self) -> ExpressionParseResult<Expression> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                Some('*') => BinaryOp::Mul,
+                Some('/') => BinaryOp::Div,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.parse_unary()?;
+            lhs = Parser::binary(op, lhs, rhs);
+        }
+    }
+
+    fn parse_unary(&mut self) -> ExpressionParseResult<Expression> {
+        if self.peek() == Some('-') {
+            self.pos += 1;
+            return Ok(Expression::Negate(Box::new(self.parse_unary()?)));
+        }
+        self.parse_power()
+    }
+
+    fn parse_power(&mut self) -> ExpressionParseResult<Expression> {
+        let base = self.parse_primary()?;
+        if self.peek() == Some('^') {
+            self.pos += 1;
+            // Powers are right associative, so `2^3^2` is `2^(3^2)`
+            let exponent = self.parse_unary()?;
+            return Ok(Parser::binary(BinaryOp::Pow, base, exponent));
+        }
+        Ok(base)
+    }
+
+    fn parse_primary(&mut self) -> ExpressionParseResult<Expression> {
+        match self.next()? {
+            '(' => {
+                let expression = self.parse_comparison()?;
+                match self.next()? {
+                    ')' => Ok(expression),
+                    c => Err(ExpressionParseError::UnexpectedCharacter(c)),
+                }
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos - 1;
+                while matches!(self.peek(), Some(c) if c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                let num: String = self.chars[start..self.pos].iter().collect();
+                num.parse()
+                    .map(Expression::Number)
+                    .map_err(|_| ExpressionParseError::InvalidNumber(num))
+            }
+            c if c.is_ascii_alphabetic() => {
+                let start = self.pos - 1;
+                while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric()) {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                match name.as_str() {
+                    "x" => Ok(Expression::X),
+                    "y" => Ok(Expression::Y),
+                    "z" => Ok(Expression::Z),
+                    _ => Err(ExpressionParseError::UnknownVariable(name)),
+                }
+            }
+            c => Err(ExpressionParseError::UnexpectedCharacter(c)),
+        }
+    }
+}
+
+#[test]
+fn expression_eval() {
+    let eval = |expr: &str| Expression::parse(expr).unwrap().eval(2.0, 3.0, 4.0);
+    assert_eq!(eval("1 + 2 * 3"), 7.0);
+    assert_eq!(eval("(1 + 2) * 3"), 9.0);
+    assert_eq!(eval("x^2 + z^2"), 20.0);
+    assert_eq!(eval("-x^2"), -4.0);
+    assert_eq!(eval("2^3^2"), 512.0);
+    assert_eq!(eval("y / 2 - 1"), 0.5);
+    assert_eq!(eval("x^2 + z^2 < 25"), 1.0);
+    assert_eq!(eval("x > y"), 0.0);
+}
+
+#[test]
+fn expression_parse_errors() {
+    assert!(matches!(
+        Expression::parse("x +"),
+        Err(ExpressionParseError::UnexpectedEnd)
+    ));
+    assert!(matches!(
+        Expression::parse("(x"),
+        Err(ExpressionParseError::UnexpectedEnd)
+    ));
+    assert!(matches!(
+        Expression::parse("w * 2"),
+        Err(ExpressionParseError::UnknownVariable(_))
+    ));
+    assert!(matches!(
+        Expression::parse("x)"),
+        Err(ExpressionParseError::UnexpectedCharacter(')'))
+    ));
+}
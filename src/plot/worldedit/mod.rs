@@ -0,0 +1,5713 @@
+mod expression;
+mod schematic;
+
+use super::{Plot, GENERATED_LAYERS};
+use crate::blocks::{Block, BlockDirection, BlockEntity, BlockFace, BlockFacing, BlockPos};
+use crate::config::CONFIG;
+use crate::items::{Item, ItemStack};
+use crate::network::packets::clientbound::{
+    C0CBossBar, C0CBossBarAction, C17PluginMessage, C21Effect, C3BMultiBlockChange,
+    C3BMultiBlockChangeRecord, ClientBoundPacket,
+};
+use crate::network::packets::{PacketBatch, PacketDecoderExt, PacketEncoderExt};
+use crate::player::Player;
+use crate::world::storage::{Chunk, PalettedBitBuffer};
+use crate::world::{Biome, World, BIOMES};
+use expression::Expression;
+use rand::Rng;
+use regex::Regex;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Attempts to execute a worldedit command. Returns true of the command was handled.
+// The command is not handled if it is not found in the worldedit commands and alias lists.
+pub fn execute_command(
+    plot: &mut Plot,
+    player_idx: usize,
+    command: &str,
+    args: &mut Vec<&str>,
+) -> bool {
+    let (name, command) = if let Some(entry) = COMMANDS.get_key_value(command) {
+        entry
+    } else if let Some(command) = ALIASES.get(command) {
+        let mut alias: Vec<&str> = command.split(' ').collect();
+        let command = alias.remove(0);
+        if alias.len() > 1 {
+            args.append(&mut alias);
+        }
+        COMMANDS.get_key_value(command).unwrap()
+    } else {
+        return false;
+    };
+
+    let player = &mut plot.players[player_idx];
+    if !has_permission(player, name) {
+        player.send_error_message(&format!("You don't have permission to use //{}.", name));
+        return true;
+    }
+    let selection = (player.first_position, player.second_position);
+
+    let mut ctx = CommandExecuteContext {
+        plot: &mut *plot,
+        player_idx,
+        arguments: Vec::new(),
+        flags: Vec::new(),
+        flag_arguments: Vec::new(),
+    };
+
+    if command.requires_positions && !check_selection(&mut ctx) {
+        return true;
+    }
+
+    if command.waits_for_queue && !check_queue_empty(&mut ctx) {
+        return true;
+    }
+
+    if command.requires_clipboard {
+        let player = ctx.get_player_mut();
+        if player.worldedit_clipboard.is_none() {
+            player.send_error_message("Your clipboard is empty. Use //copy first.");
+            return true;
+        }
+    }
+
+    let flag_descs = command.flags;
+
+    let mut arg_removal_idxs = Vec::new();
+    let mut flag_argument_idxs = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        if arg.starts_with('-') {
+            arg_removal_idxs.push(i);
+            let mut with_argument = false;
+            let flags = arg.chars();
+            for flag in flags.skip(1) {
+                if with_argument {
+                    ctx.get_player_mut()
+                        .send_error_message("Flag with argument must be last in grouping");
+                    return true;
+                }
+                let flag_desc = if let Some(desc) = flag_descs.iter().find(|d| d.letter == flag) {
+                    desc
+                } else {
+                    ctx.get_player_mut()
+                        .send_error_message(&format!("Unknown flag: {}", flag));
+                    return true;
+                };
+                if !has_permission(ctx.get_player(), &format!("{}.{}", name, flag)) {
+                    ctx.get_player_mut().send_error_message(&format!(
+                        "You don't have permission to use the -{} flag of //{}.",
+                        flag, name
+                    ));
+                    return true;
+                }
+                if let Some(arg_type) = flag_desc.argument_type {
+                    arg_removal_idxs.push(i + 1);
+                    flag_argument_idxs.push((flag, arg_type, i + 1));
+                    with_argument = true;
+                }
+                ctx.flags.push(flag);
+            }
+        }
+    }
+
+    for (flag, arg_type, idx) in flag_argument_idxs {
+        let arg = match args.get(idx) {
+            Some(arg) => *arg,
+            None => {
+                ctx.get_player_mut()
+                    .send_error_message(&format!("Flag -{} needs an argument", flag));
+                return true;
+            }
+        };
+        match Argument::parse(&ctx, arg_type, Some(arg)) {
+            Ok(arg) => ctx.flag_arguments.push((flag, arg)),
+            Err(err) => {
+                ctx.get_player_mut().send_error_message(&err.to_string());
+                return true;
+            }
+        }
+    }
+
+    for idx in arg_removal_idxs.iter().rev() {
+        args.remove(*idx);
+    }
+
+    let arg_descs = command.arguments;
+
+    if args.len() > arg_descs.len() {
+        ctx.get_player_mut()
+            .send_error_message("Too many arguments.");
+        return true;
+    }
+
+    for (i, arg_desc) in arg_descs.iter().enumerate() {
+        let arg = args.get(i).copied().or(arg_desc.default);
+        match Argument::parse(&ctx, arg_desc.argument_type, arg) {
+            Ok(default_arg) => ctx.arguments.push(default_arg),
+            Err(err) => {
+                ctx.get_player_mut().send_error_message(&err.to_string());
+                return true;
+            }
+        }
+    }
+
+    (command.execute_fn)(ctx);
+
+    let player = &plot.players[player_idx];
+    if selection != (player.first_position, player.second_position) {
+        broadcast_selection(plot, player_idx);
+    }
+    true
+}
+
+/// Whether the player may use `node`, which is either a command name like `stack` or a command
+/// name and one of its flags like `stack.a`. Every player has the `worldedit_permissions` from
+/// the config, and the `worldedit_trusted_players` (listed by name or UUID) additionally have
+/// the `worldedit_trusted_permissions`.
+fn has_permission(player: &Player, node: &str) -> bool {
+    if permission_granted(&CONFIG.worldedit_permissions, node) {
+        return true;
+    }
+    let uuid = Player::uuid_with_hyphens(player.uuid);
+    let trusted = CONFIG.worldedit_trusted_players.iter().any(|entry| {
+        entry.eq_ignore_ascii_case(&player.username) || entry.eq_ignore_ascii_case(&uuid)
+    });
+    trusted && permission_granted(&CONFIG.worldedit_trusted_permissions, node)
+}
+
+/// Nodes which aren't granted by `*` and have to be listed by name
+const EXPLICIT_PERMISSIONS: &[&str] = &["upload"];
+
+/// `*` grants every node but the `EXPLICIT_PERMISSIONS` and `<command>.*` grants a command
+/// together with all of its flags.
+fn permission_granted(permissions: &[String], node: &str) -> bool {
+    permissions.iter().any(|permission| {
+        if permission == node {
+            return true;
+        }
+        if permission == "*" {
+            return !EXPLICIT_PERMISSIONS.contains(&node);
+        }
+        match permission.strip_suffix(".*") {
+            Some(command) => node.split('.').next() == Some(command),
+            None => false,
+        }
+    })
+}
+
+/// Returns false and tells the player if there are queued jobs on the plot which haven't
+/// finished yet.
+fn check_queue_empty(ctx: &mut CommandExecuteContext<'_>) -> bool {
+    if ctx.plot.worldedit_queue.is_empty() {
+        return true;
+    }
+    ctx.get_player_mut()
+        .send_error_message("Wait for the running worldedit operations to finish first.");
+    false
+}
+
+/// Returns false and tells the player if they don't have a complete selection inside of
+/// the plot.
+fn check_selection(ctx: &mut CommandExecuteContext<'_>) -> bool {
+    let plot_x = ctx.plot.x;
+    let plot_z = ctx.plot.z;
+    let player = ctx.get_player_mut();
+    let (first_pos, second_pos) = match (player.first_position, player.second_position) {
+        (Some(first_pos), Some(second_pos)) => (first_pos, second_pos),
+        _ => {
+            player.send_error_message("Make a region selection first.");
+            return false;
+        }
+    };
+    if !Plot::in_plot_bounds(plot_x, plot_z, first_pos.x, first_pos.z) {
+        player.send_system_message("First position is outside plot bounds!");
+        return false;
+    }
+    if !Plot::in_plot_bounds(plot_x, plot_z, second_pos.x, second_pos.z) {
+        player.send_system_message("Second position is outside plot bounds!");
+        return false;
+    }
+    if !(0..=255).contains(&first_pos.y) {
+        player.send_system_message("First position is outside of the build height (0 to 255)!");
+        return false;
+    }
+    if !(0..=255).contains(&second_pos.y) {
+        player.send_system_message("Second position is outside of the build height (0 to 255)!");
+        return false;
+    }
+    true
+}
+
+/// The CUI messages that show `player`'s selection as a separate region, tagged with the
+/// player's uuid. Messages starting with `+` apply to that region instead of the player's own.
+fn selection_cui_messages(player: &Player) -> Vec<String> {
+    let mut messages = vec![format!("+s|cuboid|{:032x}", player.uuid)];
+    for point in player.worldedit_selection_cui_points() {
+        messages.push(format!("+{}", point));
+    }
+    messages
+}
+
+/// Sends the player's selection to the other players on the plot who turned on //showsel.
+pub fn broadcast_selection(plot: &mut Plot, player_idx: usize) {
+    let messages = selection_cui_messages(&plot.players[player_idx]);
+    for (i, other) in plot.players.iter_mut().enumerate() {
+        if i != player_idx && other.worldedit_show_selections {
+            for message in &messages {
+                other.worldedit_send_cui(message);
+            }
+        }
+    }
+}
+
+/// Returns the names of all the worldedit commands and aliases, without the leading `//`.
+pub fn command_names() -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = COMMANDS.keys().chain(ALIASES.keys()).copied().collect();
+    names.sort_unstable();
+    names
+}
+
+fn find_command(name: &str) -> Option<&'static WorldeditCommand> {
+    COMMANDS.get(name).or_else(|| {
+        let alias = ALIASES.get(name)?;
+        COMMANDS.get(alias.split(' ').next().unwrap())
+    })
+}
+
+/// A possible completion for a command and its tooltip
+pub type CommandCompletion = (String, Option<&'static str>);
+
+/// Completes the last word of a worldedit command. This returns the index the word starts
+/// at and the possible replacements for it, or `None` if it isn't a worldedit command.
+pub fn complete_command(player: &Player, text: &str) -> Option<(usize, Vec<CommandCompletion>)> {
+    let mut words: Vec<&str> = text.strip_prefix("//")?.split(' ').collect();
+    let last = words.pop().unwrap();
+    let start = text.len() - last.len();
+
+    if words.is_empty() {
+        let matches = command_names()
+            .into_iter()
+            .filter(|name| name.starts_with(last))
+            .map(|name| {
+                (
+                    name.to_owned(),
+                    Some(find_command(name).unwrap().description),
+                )
+            })
+            .collect();
+        return Some((start, matches));
+    }
+
+    let command = find_command(words[0])?;
+    if let Some(used_flags) = last.strip_prefix('-') {
+        let matches = command
+            .flags
+            .iter()
+            .filter(|flag| !used_flags.contains(flag.letter))
+            .map(|flag| (format!("{}{}", last, flag.letter), Some(flag.description)))
+            .collect();
+        return Some((start, matches));
+    }
+
+    let arg_idx = words[1..]
+        .iter()
+        .filter(|arg| !arg.starts_with('-'))
+        .count();
+    // The word after a flag like `-m` is the argument of that flag
+    let flag_arg_type = words[1..]
+        .last()
+        .and_then(|word| word.strip_prefix('-')?.chars().last())
+        .and_then(|letter| command.flags.iter().find(|flag| flag.letter == letter))
+        .and_then(|flag| flag.argument_type);
+    let arg_type = match flag_arg_type.or_else(|| {
+        command
+            .arguments
+            .get(arg_idx)
+            .map(|arg_desc| arg_desc.argument_type)
+    }) {
+        Some(arg_type) => arg_type,
+        None => return Some((start, Vec::new())),
+    };
+    let with_clipboard = player.worldedit_clipboard.is_some();
+    let values = match arg_type {
+        ArgumentType::Pattern => complete_pattern(last, &[',', '%'], with_clipboard),
+        ArgumentType::Mask => complete_pattern(last, &[',', '%', '!'], false),
+        ArgumentType::String if words[0] == "replace" => {
+            complete_pattern(last, &[',', '%', '=', '!'], with_clipboard)
+        }
+        ArgumentType::Direction => [
+            "me", "forward", "back", "left", "right", "north", "south", "east", "west", "up",
+            "down",
+        ]
+        .iter()
+        .filter(|direction| direction.starts_with(last))
+        .map(|direction| direction.to_string())
+        .collect(),
+        ArgumentType::Position => vec!["~,~,~".to_owned()],
+        ArgumentType::String if words[0] == "sel" => ["cuboid", "extend"]
+            .iter()
+            .filter(|selector| selector.starts_with(last))
+            .map(|selector| selector.to_string())
+            .collect(),
+        ArgumentType::String if words[0] == "snapshot" && arg_idx == 0 => {
+            ["save", "restore", "list"]
+                .iter()
+                .filter(|action| action.starts_with(last))
+                .map(|action| action.to_string())
+                .collect()
+        }
+        ArgumentType::String if matches!(words[0], "copy" | "c" | "cut" | "x") => COPY_ANCHORS
+            .iter()
+            .filter(|anchor| anchor.starts_with(last))
+            .map(|anchor| anchor.to_string())
+            .collect(),
+        ArgumentType::String if words[0] == "setbiome" => BIOMES
+            .iter()
+            .map(|biome| biome.name.trim_start_matches("minecraft:"))
+            .filter(|name| name.starts_with(last))
+            .map(|name| name.to_string())
+            .collect(),
+        ArgumentType::String if words[0] == "help" => COMMANDS
+            .keys()
+            .filter(|name| name.starts_with(last))
+            .map(|name| name.to_string())
+            .collect(),
+        ArgumentType::String if words[0] == "load" => {
+            schematic::list_schematics(Path::new(&CONFIG.schems_dir), player.uuid)
+                .into_iter()
+                .filter(|name| name.starts_with(last))
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    let matches = values.into_iter().map(|value| (value, None)).collect();
+    Some((start, matches))
+}
+
+/// Completes the block name in the last part of a pattern, which starts after the last of the
+/// `separators`.
+fn complete_pattern(last: &str, separators: &[char], with_clipboard: bool) -> Vec<String> {
+    let part_start = last.rfind(separators).map_or(0, |idx| idx + 1);
+    let (prefix, part) = last.split_at(part_start);
+    let tags = Block::TAGS.iter().map(|(tag, _)| format!("##{}", tag));
+    let mut names: Vec<String> = Block::NAMES
+        .iter()
+        .map(|name| name.to_string())
+        .chain(tags)
+        .collect();
+    if with_clipboard {
+        names.push("#clipboard".to_owned());
+    }
+    names
+        .into_iter()
+        .filter(|name| name.starts_with(part))
+        .map(|name| format!("{}{}", prefix, name))
+        .collect()
+}
+
+#[derive(Debug)]
+struct ArgumentParseError {
+    arg_type: ArgumentType,
+    reason: String,
+}
+
+impl ArgumentParseError {
+    fn new(arg_type: ArgumentType, reason: &str) -> ArgumentParseError {
+        ArgumentParseError {
+            arg_type,
+            reason: String::from(reason),
+        }
+    }
+}
+
+impl fmt::Display for ArgumentParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Error parsing argument of type {:?}: {}",
+            self.arg_type, self.reason
+        )
+    }
+}
+
+impl std::error::Error for ArgumentParseError {}
+
+type ArgumentParseResult = Result<Argument, ArgumentParseError>;
+
+#[derive(Copy, Clone, Debug)]
+enum ArgumentType {
+    UnsignedInteger,
+    Direction,
+    Mask,
+    Pattern,
+    String,
+    Position,
+}
+
+enum Argument {
+    UnsignedInteger(u32),
+    Direction(BlockFacing),
+    Position(BlockPos),
+    Pattern(WorldEditPattern),
+    Mask(WorldEditPattern),
+    String(String),
+}
+
+impl Argument {
+    fn unwrap_uint(&self) -> u32 {
+        match self {
+            Argument::UnsignedInteger(val) => *val,
+            _ => panic!("Argument was not an UnsignedInteger"),
+        }
+    }
+
+    fn unwrap_direction(&self) -> &BlockFacing {
+        match self {
+            Argument::Direction(val) => val,
+            _ => panic!("Argument was not an Direction"),
+        }
+    }
+
+    fn unwrap_position(&self) -> BlockPos {
+        match self {
+            Argument::Position(val) => *val,
+            _ => panic!("Argument was not a Position"),
+        }
+    }
+
+    fn unwrap_pattern(&self) -> &WorldEditPattern {
+        match self {
+            Argument::Pattern(val) => val,
+            _ => panic!("Argument was not a Pattern"),
+        }
+    }
+
+    fn unwrap_mask(&self) -> &WorldEditPattern {
+        match self {
+            Argument::Mask(val) => val,
+            _ => panic!("Argument was not a Mask"),
+        }
+    }
+
+    fn unwrap_string(&self) -> &String {
+        match self {
+            Argument::String(val) => val,
+            _ => panic!("Argument was not a String"),
+        }
+    }
+
+    fn get_default(ctx: &CommandExecuteContext<'_>, arg_type: ArgumentType) -> ArgumentParseResult {
+        match arg_type {
+            ArgumentType::Direction => Argument::parse(ctx, arg_type, Some("me")),
+            ArgumentType::UnsignedInteger => Ok(Argument::UnsignedInteger(1)),
+            ArgumentType::Position => Argument::parse(ctx, arg_type, Some("~,~,~")),
+            _ => Err(ArgumentParseError::new(
+                arg_type,
+                "argument can't be inferred",
+            )),
+        }
+    }
+
+    fn parse(
+        ctx: &CommandExecuteContext<'_>,
+        arg_type: ArgumentType,
+        arg: Option<&str>,
+    ) -> ArgumentParseResult {
+        if arg.is_none() {
+            return Argument::get_default(ctx, arg_type);
+        }
+        let arg = arg.unwrap();
+        match arg_type {
+            ArgumentType::Direction => {
+                let player = ctx.get_player();
+                if let Some(facing) =
+                    relative_direction(arg, player.get_facing(), player.get_direction())
+                {
+                    return Ok(Argument::Direction(facing));
+                }
+                let facing = match arg {
+                    "north" | "n" => BlockFacing::North,
+                    "south" | "s" => BlockFacing::South,
+                    "east" | "e" => BlockFacing::East,
+                    "west" | "w" => BlockFacing::West,
+                    "up" | "u" => BlockFacing::Up,
+                    "down" | "d" => BlockFacing::Down,
+                    _ => return Err(ArgumentParseError::new(arg_type, "unknown direction")),
+                };
+                Ok(Argument::Direction(facing))
+            }
+            ArgumentType::UnsignedInteger => match arg.parse::<u32>() {
+                Ok(num) => Ok(Argument::UnsignedInteger(num)),
+                Err(_) => Err(ArgumentParseError::new(arg_type, "error parsing uint")),
+            },
+            ArgumentType::Pattern => match WorldEditPattern::from_str_with_clipboard(
+                arg,
+                ctx.get_player().worldedit_clipboard.as_ref(),
+            ) {
+                Ok(pattern) => Ok(Argument::Pattern(pattern)),
+                Err(err) => Err(ArgumentParseError::new(arg_type, &err.to_string())),
+            },
+            ArgumentType::Mask => match WorldEditPattern::mask_from_str(arg) {
+                Ok(pattern) => Ok(Argument::Mask(pattern)),
+                Err(err) => Err(ArgumentParseError::new(arg_type, &err.to_string())),
+            },
+            ArgumentType::String => Ok(Argument::String(arg.to_owned())),
+            ArgumentType::Position => {
+                let player = ctx.get_player();
+                let player_pos = BlockPos::new(
+                    player.x.floor() as i32,
+                    player.y.floor() as i32,
+                    player.z.floor() as i32,
+                );
+                match parse_position(arg, player_pos) {
+                    Some(pos) => Ok(Argument::Position(pos)),
+                    None => Err(ArgumentParseError::new(
+                        arg_type,
+                        "expected x,y,z (use ~ for coordinates relative to you)",
+                    )),
+                }
+            }
+        }
+    }
+}
+
+struct ArgumentDescription {
+    name: &'static str,
+    argument_type: ArgumentType,
+    description: &'static str,
+    /// Used in place of the argument when it is missing
+    default: Option<&'static str>,
+}
+
+macro_rules! argument {
+    ($name:literal, $type:ident, $desc:literal) => {
+        ArgumentDescription {
+            name: $name,
+            argument_type: ArgumentType::$type,
+            description: $desc,
+            default: None,
+        }
+    };
+    ($name:literal, $type:ident, $desc:literal, default: $default:literal) => {
+        ArgumentDescription {
+            name: $name,
+            argument_type: ArgumentType::$type,
+            description: $desc,
+            default: Some($default),
+        }
+    };
+}
+
+struct FlagDescription {
+    letter: char,
+    argument_type: Option<ArgumentType>,
+    description: &'static str,
+}
+
+macro_rules! flag {
+    ($name:literal, $type:expr, $desc:literal) => {
+        FlagDescription {
+            letter: $name,
+            argument_type: $type,
+            description: $desc,
+        }
+    };
+}
+
+struct CommandExecuteContext<'a> {
+    plot: &'a mut Plot,
+    player_idx: usize,
+    arguments: Vec<Argument>,
+    flags: Vec<char>,
+    flag_arguments: Vec<(char, Argument)>,
+}
+
+impl<'a> CommandExecuteContext<'a> {
+    fn has_flag(&self, c: char) -> bool {
+        self.flags.contains(&c)
+    }
+
+    /// The argument given to the flag `c`, if it was used.
+    fn get_flag_argument(&self, c: char) -> Option<&Argument> {
+        self.flag_arguments
+            .iter()
+            .find(|(flag, _)| *flag == c)
+            .map(|(_, arg)| arg)
+    }
+
+    fn get_player(&self) -> &Player {
+        &self.plot.players[self.player_idx]
+    }
+
+    fn get_player_mut(&mut self) -> &mut Player {
+        &mut self.plot.players[self.player_idx]
+    }
+}
+
+struct WorldeditCommand {
+    arguments: &'static [ArgumentDescription],
+    flags: &'static [FlagDescription],
+    requires_positions: bool,
+    requires_clipboard: bool,
+    /// The command changes blocks right away, so it can't run while a queued job is only
+    /// partly done. The job would overwrite its changes once it gets to them.
+    waits_for_queue: bool,
+    execute_fn: fn(CommandExecuteContext<'_>),
+    description: &'static str,
+}
+
+impl Default for WorldeditCommand {
+    fn default() -> Self {
+        Self {
+            arguments: &[],
+            flags: &[],
+            execute_fn: execute_unimplemented,
+            description: "",
+            requires_clipboard: false,
+            requires_positions: false,
+            waits_for_queue: false,
+        }
+    }
+}
+
+lazy_static! {
+    static ref COMMANDS: HashMap<&'static str, WorldeditCommand> = map! {
+        "copy" => WorldeditCommand {
+            arguments: &[
+                argument!("anchor", String, "Where the clipboard is anchored: me, center, min, max, pos1 or pos2", default: "me")
+            ],
+            requires_positions: true,
+            execute_fn: execute_copy,
+            description: "Copy the selection to the clipboard",
+            flags: &[
+                flag!('t', None, "Trim the air around the copied blocks")
+            ],
+            ..Default::default()
+        },
+        "cut" => WorldeditCommand {
+            arguments: &[
+                argument!("anchor", String, "Where the clipboard is anchored: me, center, min, max, pos1 or pos2", default: "me")
+            ],
+            requires_positions: true,
+            execute_fn: execute_cut,
+            waits_for_queue: true,
+            description: "Cut the selection to the clipboard",
+            flags: &[
+                flag!('n', None, "Only report how many blocks would be cut")
+            ],
+            ..Default::default()
+        },
+        "paste" => WorldeditCommand {
+            requires_clipboard: true,
+            execute_fn: execute_paste,
+            waits_for_queue: true,
+            description: "Paste the clipboard's contents",
+            flags: &[
+                flag!('a', None, "Skip air blocks"),
+                flag!('t', None, "Show how long each phase of the operation took"),
+                flag!('m', Some(ArgumentType::Mask), "Only paste the blocks matching this mask")
+            ],
+            ..Default::default()
+        },
+        "clearclipboard" => WorldeditCommand {
+            execute_fn: execute_clearclipboard,
+            description: "Clear your clipboard",
+            ..Default::default()
+        },
+        "undo" => WorldeditCommand {
+            arguments: &[
+                argument!("count", UnsignedInteger, "# of operations to undo")
+            ],
+            execute_fn: execute_undo,
+            waits_for_queue: true,
+            description: "Undo's the last action (from history)",
+            ..Default::default()
+        },
+        "stack" => WorldeditCommand {
+            arguments: &[
+                argument!("count", UnsignedInteger, "# of copies to stack"),
+                argument!("direction", Direction, "The direction to stack")
+            ],
+            requires_positions: true,
+            execute_fn: execute_stack,
+            waits_for_queue: true,
+            description: "Repeat the contents of the selection",
+            flags: &[
+                flag!('a', None, "Ignore air blocks")
+            ],
+            ..Default::default()
+        },
+        "move" => WorldeditCommand {
+            arguments: &[
+                argument!("count", UnsignedInteger, "The distance to move"),
+                argument!("direction", Direction, "The direction to move")
+            ],
+            requires_positions: true,
+            execute_fn: execute_move,
+            waits_for_queue: true,
+            description: "Move the contents of the selection",
+            flags: &[
+                flag!('a', None, "Ignore air blocks"),
+                flag!('s', None, "Shift the selection to the target location"),
+                flag!('t', None, "Show how long each phase of the operation took")
+            ],
+            ..Default::default()
+        },
+        "rotate" => WorldeditCommand {
+            arguments: &[
+                argument!("degrees", UnsignedInteger, "Amount to rotate clockwise, in multiples of 90")
+            ],
+            execute_fn: execute_rotate,
+            description: "Rotate the contents of the clipboard",
+            flags: &[
+                flag!('s', None, "Rotate the selection in place instead of the clipboard")
+            ],
+            ..Default::default()
+        },
+        "flip" => WorldeditCommand {
+            arguments: &[
+                argument!("direction", Direction, "The direction to flip, defaults to look direction")
+            ],
+            execute_fn: execute_flip,
+            description: "Flip the contents of the clipboard across the origin",
+            flags: &[
+                flag!('s', None, "Flip the selection in place instead of the clipboard")
+            ],
+            ..Default::default()
+        },
+        "replacenear" => WorldeditCommand {
+            arguments: &[
+                argument!("size", UnsignedInteger, "The distance from you to replace blocks in"),
+                argument!("from", Mask, "The mask representing blocks to replace"),
+                argument!("to", Pattern, "The pattern of blocks to replace with")
+            ],
+            execute_fn: execute_replacenear,
+            waits_for_queue: true,
+            description: "Replace nearby blocks",
+            flags: &[
+                flag!('t', None, "Show how long each phase of the operation took")
+            ],
+            ..Default::default()
+        },
+        "removenear" => WorldeditCommand {
+            arguments: &[
+                argument!("mask", Mask, "The mask of blocks to remove"),
+                argument!("size", UnsignedInteger, "The distance from you to remove blocks in", default: "50")
+            ],
+            execute_fn: execute_removenear,
+            waits_for_queue: true,
+            description: "Remove nearby blocks matching a mask",
+            flags: &[
+                flag!('t', None, "Show how long each phase of the operation took")
+            ],
+            ..Default::default()
+        },
+        "count" => WorldeditCommand {
+            arguments: &[
+                argument!("mask", Mask, "The mask of blocks to match")
+            ],
+            execute_fn: execute_count,
+            description: "Counts the number of blocks matching a mask",
+            flags: &[
+                flag!('d', None, "Break the count down by block type"),
+                flag!('c', None, "Count the blocks in the clipboard instead of the selection")
+            ],
+            ..Default::default()
+        },
+        "sel" => WorldeditCommand {
+            arguments: &[
+                argument!("selector", String, "The selector to switch to, either cuboid or extend", default: "")
+            ],
+            execute_fn: execute_sel,
+            description: "Choose a region selector",
+            ..Default::default()
+        },
+        "snapshot" => WorldeditCommand {
+            arguments: &[
+                argument!("action", String, "Either save, restore or list"),
+                argument!("name", String, "The name of the snapshot", default: "")
+            ],
+            execute_fn: execute_snapshot,
+            description: "Save the selection as a named snapshot or restore one",
+            ..Default::default()
+        },
+        "set" => WorldeditCommand {
+            arguments: &[
+                argument!("pattern", Pattern, "The pattern of blocks to set")
+            ],
+            requires_positions: true,
+            execute_fn: execute_set,
+            waits_for_queue: true,
+            description: "Sets all the blocks in the region",
+            flags: &[
+                flag!('u', None, "Update the blocks after setting them"),
+                flag!('t', None, "Show how long each phase of the operation took"),
+                flag!('n', None, "Only report how many blocks would be changed")
+            ],
+            ..Default::default()
+        },
+        "wand" => WorldeditCommand {
+            execute_fn: execute_wand,
+            description: "Get the wand item",
+            ..Default::default()
+        },
+        "showsel" => WorldeditCommand {
+            execute_fn: execute_showsel,
+            description: "Toggle showing the selections of other players on the plot",
+            ..Default::default()
+        },
+        "toggleeditwand" => WorldeditCommand {
+            execute_fn: execute_toggleeditwand,
+            description: "Toggle functionality of the edit wand",
+            ..Default::default()
+        },
+        "fixlighting" => WorldeditCommand {
+            requires_positions: true,
+            execute_fn: execute_fixlighting,
+            description: "Resend the light of the chunks in the selection",
+            ..Default::default()
+        },
+        "setbiome" => WorldeditCommand {
+            arguments: &[
+                argument!("biome", String, "The biome to set")
+            ],
+            requires_positions: true,
+            execute_fn: execute_setbiome,
+            description: "Set the biome of the columns in the selection",
+            ..Default::default()
+        },
+        "smooth" => WorldeditCommand {
+            arguments: &[
+                argument!("iterations", UnsignedInteger, "# of iterations to perform")
+            ],
+            requires_positions: true,
+            execute_fn: execute_smooth,
+            waits_for_queue: true,
+            description: "Smooth the elevation in the selection",
+            ..Default::default()
+        },
+        "naturalize" => WorldeditCommand {
+            requires_positions: true,
+            execute_fn: execute_naturalize,
+            waits_for_queue: true,
+            description: "3 layers of dirt on top then rock below",
+            ..Default::default()
+        },
+        "snow" => WorldeditCommand {
+            arguments: &[
+                argument!("radius", UnsignedInteger, "The radius of the cylinder to snow in", default: "10")
+            ],
+            execute_fn: execute_snow,
+            waits_for_queue: true,
+            description: "Simulates snow",
+            ..Default::default()
+        },
+        "thaw" => WorldeditCommand {
+            arguments: &[
+                argument!("radius", UnsignedInteger, "The radius of the cylinder to thaw in", default: "10")
+            ],
+            execute_fn: execute_thaw,
+            waits_for_queue: true,
+            description: "Thaws the area",
+            ..Default::default()
+        },
+        "green" => WorldeditCommand {
+            arguments: &[
+                argument!("radius", UnsignedInteger, "The radius of the cylinder to convert in", default: "10")
+            ],
+            execute_fn: execute_green,
+            waits_for_queue: true,
+            description: "Converts dirt to grass blocks in the area",
+            ..Default::default()
+        },
+        "regen" => WorldeditCommand {
+            requires_positions: true,
+            execute_fn: execute_regen,
+            waits_for_queue: true,
+            description: "Regenerates the contents of the selection",
+            ..Default::default()
+        },
+        "chunk" => WorldeditCommand {
+            execute_fn: execute_chunk,
+            description: "Set the selection to your current chunk.",
+            ..Default::default()
+        },
+        "pos1" => WorldeditCommand {
+            arguments: &[
+                argument!("coordinates", Position, "The position to set")
+            ],
+            execute_fn: execute_pos1,
+            description: "Set position 1",
+            ..Default::default()
+        },
+        "pos2" => WorldeditCommand {
+            arguments: &[
+                argument!("coordinates", Position, "The position to set")
+            ],
+            execute_fn: execute_pos2,
+            description: "Set position 2",
+            ..Default::default()
+        },
+        "setblock" => WorldeditCommand {
+            arguments: &[
+                argument!("coordinates", Position, "The position of the block"),
+                argument!("pattern", Pattern, "The block to set")
+            ],
+            execute_fn: execute_setblock,
+            waits_for_queue: true,
+            description: "Sets a single block without using the selection",
+            ..Default::default()
+        },
+        "hpos1" => WorldeditCommand {
+            execute_fn: execute_hpos1,
+            description: "Set position 1 to targeted block",
+            ..Default::default()
+        },
+        "hpos2" => WorldeditCommand {
+            execute_fn: execute_hpos2,
+            description: "Set position 2 to targeted block",
+            ..Default::default()
+        },
+        "info" => WorldeditCommand {
+            execute_fn: execute_info,
+            description: "Show the id and properties of the targeted block",
+            ..Default::default()
+        },
+        "replace" => WorldeditCommand {
+            arguments: &[
+                argument!("from", String, "The mask representng blocks to replace, or from=to pairs separated by commas"),
+                argument!("to", String, "The pattern of blocks to replace with", default: "")
+            ],
+            requires_positions: true,
+            execute_fn: execute_replace,
+            description: "Replace all blocks in a selection with another",
+            flags: &[
+                flag!('u', None, "Update the blocks after replacing them"),
+                flag!('k', None, "Keep the block entities of replaced blocks where possible"),
+                flag!('t', None, "Show how long each phase of the operation took"),
+                flag!('n', None, "Only report how many blocks would be changed"),
+                flag!('p', None, "Keep the properties of replaced blocks, like which way they face")
+            ],
+            ..Default::default()
+        },
+        "fill" => WorldeditCommand {
+            arguments: &[
+                argument!("pattern", Pattern, "The blocks to fill with"),
+                argument!("radius", UnsignedInteger, "The radius to fill in"),
+                argument!("depth", UnsignedInteger, "The depth to fill")
+            ],
+            execute_fn: execute_fill,
+            waits_for_queue: true,
+            description: "Fill a hole",
+            ..Default::default()
+        },
+        "sphere" => WorldeditCommand {
+            arguments: &[
+                argument!("pattern", Pattern, "The pattern of blocks to set"),
+                argument!("radii", String, "The radii of the sphere, either one for all axes or x,y,z")
+            ],
+            execute_fn: execute_sphere,
+            waits_for_queue: true,
+            description: "Generate a filled sphere",
+            ..Default::default()
+        },
+        "generate" => WorldeditCommand {
+            arguments: &[
+                argument!("pattern", Pattern, "The pattern of blocks to set"),
+                argument!("expression", String, "The expression to use")
+            ],
+            requires_positions: true,
+            execute_fn: execute_generate,
+            waits_for_queue: true,
+            description: "Generates a shape according to a formula",
+            ..Default::default()
+        },
+        "deform" => WorldeditCommand {
+            arguments: &[
+                argument!("x", String, "The expression for the x coordinate each block is taken from", default: "x"),
+                argument!("y", String, "The expression for the y coordinate each block is taken from", default: "y"),
+                argument!("z", String, "The expression for the z coordinate each block is taken from", default: "z")
+            ],
+            requires_positions: true,
+            execute_fn: execute_deform,
+            waits_for_queue: true,
+            description: "Deforms the selection according to a formula",
+            ..Default::default()
+        },
+        "pyramid" => WorldeditCommand {
+            arguments: &[
+                argument!("pattern", Pattern, "The pattern of blocks to set"),
+                argument!("size", UnsignedInteger, "The size of the pyramid")
+            ],
+            execute_fn: execute_pyramid,
+            waits_for_queue: true,
+            description: "Generate a filled pyramid",
+            ..Default::default()
+        },
+        "hpyramid" => WorldeditCommand {
+            arguments: &[
+                argument!("pattern", Pattern, "The pattern of blocks to set"),
+                argument!("size", UnsignedInteger, "The size of the pyramid")
+            ],
+            execute_fn: execute_hpyramid,
+            waits_for_queue: true,
+            description: "Generate a hollow pyramid",
+            ..Default::default()
+        },
+        "limit" => WorldeditCommand {
+            arguments: &[
+                argument!("limit", String, "The maximum number of blocks, or default to reset it", default: "default")
+            ],
+            execute_fn: execute_limit,
+            description: "Modify block change limit",
+            ..Default::default()
+        },
+        "help" => WorldeditCommand {
+            arguments: &[
+                argument!("command", String, "The command to describe or the page of commands to list", default: "1")
+            ],
+            execute_fn: execute_help,
+            description: "Displays help for worldedit commands",
+            ..Default::default()
+        },
+        "load" => WorldeditCommand {
+            arguments: &[
+                argument!("name", String, "The file name of the schematic to load")
+            ],
+            execute_fn: execute_load,
+            description: "Loads a schematic file into the clipboard",
+            ..Default::default()
+        },
+        "download" => WorldeditCommand {
+            requires_clipboard: true,
+            execute_fn: execute_download,
+            description: "Sends the clipboard to your client as a schematic",
+            ..Default::default()
+        }
+    };
+}
+
+lazy_static! {
+    static ref ALIASES: HashMap<&'static str, &'static str> = map! {
+        "1" => "pos1",
+        "2" => "pos2",
+        "c" => "copy",
+        "x" => "cut",
+        "v" => "paste",
+        "va" => "paste -a",
+        "s" => "stack",
+        "sa" => "stack -a",
+        "g" => "generate",
+        "cc" => "clearclipboard",
+        "relight" => "fixlighting"
+    };
+}
+
+#[derive(Clone, Debug)]
+pub enum WorldEditPatternPart {
+    Block {
+        weight: f32,
+        block_id: u32,
+        /// The block ids that this part matches when it's used as a mask. A block written
+        /// without properties matches all of its states.
+        matching_ids: RangeInclusive<u32>,
+    },
+    /// `#clipboard` or `#copy`: The blocks of the clipboard, repeated across the world
+    Clipboard {
+        weight: f32,
+        clipboard: Arc<WorldEditClipboard>,
+    },
+}
+
+impl WorldEditPatternPart {
+    fn weight(&self) -> f32 {
+        match self {
+            WorldEditPatternPart::Block { weight, .. } => *weight,
+            WorldEditPatternPart::Clipboard { weight, .. } => *weight,
+        }
+    }
+
+    fn pick_at(&self, pos: BlockPos) -> Block {
+        match self {
+            WorldEditPatternPart::Block { block_id, .. } => Block::from_id(*block_id),
+            WorldEditPatternPart::Clipboard { clipboard, .. } => {
+                let x = pos.x.rem_euclid(clipboard.size_x as i32);
+                let y = pos.y.rem_euclid(clipboard.size_y as i32);
+                let z = pos.z.rem_euclid(clipboard.size_z as i32);
+                Block::from_id(clipboard.data.get_entry(clipboard.index(x, y, z)))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WorldEditClipboard {
+    pub offset_x: i32,
+    pub offset_y: i32,
+    pub offset_z: i32,
+    pub size_x: u32,
+    pub size_y: u32,
+    pub size_z: u32,
+    pub data: PalettedBitBuffer,
+    pub block_entities: HashMap<BlockPos, BlockEntity>,
+    // TODO: Copy entities (item frames, armor stands, etc.) with a `-e` flag on //copy and //cut.
+    // Plots don't keep track of any entities other than players yet, so there is nothing to
+    // capture here. Once they do, they can be stored relative to the clipboard like block
+    // entities and spawned with `C00SpawnEntity`/`C02SpawnLivingEntity` in `paste_clipboard`.
+}
+
+/// The plugin channel clipboards are transferred on. Every message starts with the index of the
+/// part it carries and the number of parts as varints, followed by that part of a gzipped
+/// schematic.
+pub const CLIPBOARD_CHANNEL: &str = "mchprs:clipboard";
+/// Serverbound plugin messages can carry at most 32767 bytes, so schematics are split into parts
+/// that fit in both directions
+const CLIPBOARD_PART_SIZE: usize = 32_000;
+/// The largest schematic a client is allowed to upload
+const MAX_CLIPBOARD_UPLOAD: usize = 16 * 1024 * 1024;
+
+/// A schematic the client is uploading over `CLIPBOARD_CHANNEL`
+#[derive(Debug, Default)]
+pub struct ClipboardUpload {
+    next_part: i32,
+    data: Vec<u8>,
+}
+
+impl ClipboardUpload {
+    /// Adds the part in `message` to the upload. The whole schematic is returned once the
+    /// last part arrived. Part 0 always starts a new upload.
+    fn receive_part(&mut self, mut message: &[u8]) -> Result<Option<Vec<u8>>, &'static str> {
+        let part = message
+            .read_varint()
+            .map_err(|_| "the message is malformed")?;
+        let parts = message
+            .read_varint()
+            .map_err(|_| "the message is malformed")?;
+        if part == 0 {
+            self.next_part = 0;
+            self.data.clear();
+        }
+        if part != self.next_part || part >= parts {
+            return Err("the parts arrived out of order");
+        }
+        if self.data.len() + message.len() > MAX_CLIPBOARD_UPLOAD {
+            return Err("the schematic is too large");
+        }
+        self.data.extend_from_slice(message);
+        self.next_part += 1;
+        if self.next_part < parts {
+            return Ok(None);
+        }
+        self.next_part = 0;
+        Ok(Some(std::mem::take(&mut self.data)))
+    }
+}
+
+/// Splits a schematic into the plugin messages it's sent to the client with
+fn clipboard_messages(schematic: &[u8]) -> Vec<Vec<u8>> {
+    let parts: Vec<&[u8]> = schematic.chunks(CLIPBOARD_PART_SIZE).collect();
+    parts
+        .iter()
+        .enumerate()
+        .map(|(idx, part)| {
+            let mut message = Vec::with_capacity(part.len() + 10);
+            message.write_varint(idx as i32);
+            message.write_varint(parts.len() as i32);
+            message.extend_from_slice(part);
+            message
+        })
+        .collect()
+}
+
+/// Handles a part of a clipboard the player's client sent over `CLIPBOARD_CHANNEL`
+pub fn receive_clipboard_part(player: &mut Player, message: &[u8]) {
+    if !has_permission(player, "upload") {
+        // Only the first part of an upload is answered so that the player isn't flooded
+        if message.first() == Some(&0) {
+            player.send_error_message("You don't have permission to upload schematics.");
+        }
+        return;
+    }
+    let schematic = match player.worldedit_clipboard_upload.receive_part(message) {
+        Ok(Some(schematic)) => schematic,
+        Ok(None) => return,
+        Err(err) => {
+            player.worldedit_clipboard_upload = ClipboardUpload::default();
+            player.send_error_message(&format!("Your clipboard could not be uploaded: {}", err));
+            return;
+        }
+    };
+    match WorldEditClipboard::from_schematic_bytes(&schematic) {
+        Ok(cb) => {
+            player.worldedit_clipboard = Some(Arc::new(cb));
+            player.send_worldedit_message(
+                "The uploaded schematic was loaded to your clipboard. Do //paste to birth it into the world.",
+            );
+        }
+        Err(err) => {
+            player.send_error_message(&format!(
+                "There was an error loading the uploaded schematic: {}",
+                err
+            ));
+        }
+    }
+}
+
+/// How the wand and the position commands build up a selection
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelectionMode {
+    /// The first and second positions are the corners of the selection
+    Cuboid,
+    /// The first position starts a new selection and the second position grows it
+    Extend,
+}
+
+#[derive(Clone, Debug)]
+pub struct WorldEditUndo {
+    changes: WorldEditUndoChanges,
+    plot_x: i32,
+    plot_z: i32,
+}
+
+#[derive(Clone, Debug)]
+enum WorldEditUndoChanges {
+    /// The whole region as it was before the operation. This is cheaper for operations
+    /// that change most of the blocks in their region.
+    Snapshot {
+        clipboard: WorldEditClipboard,
+        pos: BlockPos,
+    },
+    /// Only the blocks that the operation changed, in the order they were changed
+    Blocks(Vec<UndoBlockChange>),
+}
+
+/// The state of a block from before an operation changed it
+#[derive(Clone, Debug)]
+struct UndoBlockChange {
+    pos: BlockPos,
+    block_id: u32,
+    block_entity: Option<BlockEntity>,
+}
+
+impl WorldEditClipboard {
+    fn index(&self, x: i32, y: i32, z: i32) -> usize {
+        ((y as u32 * self.size_z + z as u32) * self.size_x + x as u32) as usize
+    }
+
+    /// Whether `pos`, relative to the clipboard, is one of the clipboard's blocks
+    fn contains(&self, pos: BlockPos) -> bool {
+        (0..self.size_x as i32).contains(&pos.x)
+            && (0..self.size_y as i32).contains(&pos.y)
+            && (0..self.size_z as i32).contains(&pos.z)
+    }
+
+    /// Moves the blocks between `start` and `end` (relative to the clipboard) by `delta`.
+    /// The area that was left behind is filled with air. The source is read completely
+    /// before anything is written, so the source and destination are allowed to overlap.
+    fn shift_region(&mut self, start: BlockPos, end: BlockPos, delta: BlockPos, ignore_air: bool) {
+        let mut blocks = Vec::new();
+        for y in start.y..=end.y {
+            for z in start.z..=end.z {
+                for x in start.x..=end.x {
+                    let idx = self.index(x, y, z);
+                    blocks.push((BlockPos::new(x, y, z), self.data.get_entry(idx)));
+                    self.data.set_entry(idx, 0);
+                }
+            }
+        }
+
+        let in_source = |pos: &BlockPos| {
+            (start.x..=end.x).contains(&pos.x)
+                && (start.y..=end.y).contains(&pos.y)
+                && (start.z..=end.z).contains(&pos.z)
+        };
+        let mut block_entities = HashMap::new();
+        for (pos, block_entity) in self.block_entities.drain() {
+            if in_source(&pos) {
+                block_entities.insert(pos + delta, block_entity);
+            } else {
+                block_entities.insert(pos, block_entity);
+            }
+        }
+        self.block_entities = block_entities;
+
+        for (pos, id) in blocks {
+            if ignore_air && id == 0 {
+                continue;
+            }
+            let pos = pos + delta;
+            let idx = self.index(pos.x, pos.y, pos.z);
+            self.data.set_entry(idx, id);
+        }
+    }
+
+    /// Creates a clipboard of the given size with every block moved to where `map_pos` puts it
+    /// and changed by `map_block`. The offset is moved along with the blocks.
+    fn transform(
+        &self,
+        (size_x, size_y, size_z): (u32, u32, u32),
+        map_pos: impl Fn(BlockPos) -> BlockPos,
+        map_block: impl Fn(Block) -> Block,
+    ) -> WorldEditClipboard {
+        let offset = map_pos(BlockPos::new(self.offset_x, self.offset_y, self.offset_z));
+        let mut cb = WorldEditClipboard {
+            offset_x: offset.x,
+            offset_y: offset.y,
+            offset_z: offset.z,
+            size_x,
+            size_y,
+            size_z,
+            data: PalettedBitBuffer::with_entries((size_x * size_y * size_z) as usize),
+            block_entities: HashMap::new(),
+        };
+        for y in 0..self.size_y as i32 {
+            for z in 0..self.size_z as i32 {
+                for x in 0..self.size_x as i32 {
+                    let block = Block::from_id(self.data.get_entry(self.index(x, y, z)));
+                    let pos = map_pos(BlockPos::new(x, y, z));
+                    let idx = cb.index(pos.x, pos.y, pos.z);
+                    cb.data.set_entry(idx, map_block(block).get_id());
+                }
+            }
+        }
+        for (pos, block_entity) in &self.block_entities {
+            cb.block_entities
+                .insert(map_pos(*pos), block_entity.clone());
+        }
+        cb
+    }
+
+    /// Rotates the clipboard 90 degrees clockwise around the y axis `turns` times.
+    fn rotate(&self, turns: u32) -> WorldEditClipboard {
+        let mut cb = self.clone();
+        for _ in 0..turns % 4 {
+            let size_z = cb.size_z as i32;
+            cb = cb.transform(
+                (cb.size_z, cb.size_y, cb.size_x),
+                |pos| BlockPos::new(size_z - 1 - pos.z, pos.y, pos.x),
+                Block::rotate,
+            );
+        }
+        cb
+    }
+
+    /// Shrinks the clipboard to the smallest box containing all of its non-air blocks. The
+    /// offset is moved so that the blocks still paste at the same place. Returns `None` if the
+    /// clipboard only contains air.
+    fn trim(&self) -> Option<WorldEditClipboard> {
+        let mut bounds: Option<(BlockPos, BlockPos)> = None;
+        for y in 0..self.size_y as i32 {
+            for z in 0..self.size_z as i32 {
+                for x in 0..self.size_x as i32 {
+                    if self.data.get_entry(self.index(x, y, z)) == 0 {
+                        continue;
+                    }
+                    let pos = BlockPos::new(x, y, z);
+                    bounds = Some(match bounds {
+                        Some((min, max)) => (min.min(pos), max.max(pos)),
+                        None => (pos, pos),
+                    });
+                }
+            }
+        }
+        let (min, max) = bounds?;
+        let size = max - min + BlockPos::new(1, 1, 1);
+        let mut cb = WorldEditClipboard {
+            offset_x: self.offset_x - min.x,
+            offset_y: self.offset_y - min.y,
+            offset_z: self.offset_z - min.z,
+            size_x: size.x as u32,
+            size_y: size.y as u32,
+            size_z: size.z as u32,
+            data: PalettedBitBuffer::with_entries((size.x * size.y * size.z) as usize),
+            block_entities: HashMap::new(),
+        };
+        for y in 0..size.y {
+            for z in 0..size.z {
+                for x in 0..size.x {
+                    let id = self
+                        .data
+                        .get_entry(self.index(x + min.x, y + min.y, z + min.z));
+                    cb.data.set_entry(cb.index(x, y, z), id);
+                }
+            }
+        }
+        // Block entities can only be in non-air blocks, so they're all inside of the bounds
+        for (pos, block_entity) in &self.block_entities {
+            cb.block_entities.insert(*pos - min, block_entity.clone());
+        }
+        Some(cb)
+    }
+
+    /// Creates a clipboard of the same size where every block is copied from the position that
+    /// `source` maps it to. Blocks that would come from outside of the clipboard become air.
+    fn deform(&self, source: impl Fn(BlockPos) -> BlockPos) -> WorldEditClipboard {
+        let mut cb = WorldEditClipboard {
+            data: PalettedBitBuffer::with_entries(self.data.entries()),
+            block_entities: HashMap::new(),
+            ..*self
+        };
+        for y in 0..self.size_y as i32 {
+            for z in 0..self.size_z as i32 {
+                for x in 0..self.size_x as i32 {
+                    let pos = BlockPos::new(x, y, z);
+                    let from = source(pos);
+                    if from.x < 0
+                        || from.y < 0
+                        || from.z < 0
+                        || from.x >= self.size_x as i32
+                        || from.y >= self.size_y as i32
+                        || from.z >= self.size_z as i32
+                    {
+                        continue;
+                    }
+                    let id = self.data.get_entry(self.index(from.x, from.y, from.z));
+                    cb.data.set_entry(cb.index(x, y, z), id);
+                    if let Some(block_entity) = self.block_entities.get(&from) {
+                        cb.block_entities.insert(pos, block_entity.clone());
+                    }
+                }
+            }
+        }
+        cb
+    }
+
+    /// Turns the clipboard upside down.
+    fn flip_vertical(&self) -> WorldEditClipboard {
+        let size_y = self.size_y as i32;
+        self.transform(
+            (self.size_x, self.size_y, self.size_z),
+            |pos| BlockPos::new(pos.x, size_y - 1 - pos.y, pos.z),
+            Block::flip_vertical,
+        )
+    }
+
+    /// Mirrors the clipboard along the axis of `direction`.
+    fn flip(&self, direction: BlockDirection) -> WorldEditClipboard {
+        let size_x = self.size_x as i32;
+        let size_z = self.size_z as i32;
+        let size = (self.size_x, self.size_y, self.size_z);
+        match direction {
+            BlockDirection::East | BlockDirection::West => self.transform(
+                size,
+                |pos| BlockPos::new(size_x - 1 - pos.x, pos.y, pos.z),
+                |block| block.flip(direction),
+            ),
+            BlockDirection::North | BlockDirection::South => self.transform(
+                size,
+                |pos| BlockPos::new(pos.x, pos.y, size_z - 1 - pos.z),
+                |block| block.flip(direction),
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PatternParseError {
+    UnknownBlock(String),
+    UnknownTag(String),
+    InvalidPattern(String),
+    UnknownToken(String),
+    UnknownProperty(String),
+    EmptyClipboard,
+    ZeroWeight,
+    MissingMapping(String),
+}
+
+impl fmt::Display for PatternParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternParseError::UnknownBlock(block) => write!(f, "unknown block: {}", block),
+            PatternParseError::UnknownTag(tag) => write!(f, "unknown block tag: {}", tag),
+            PatternParseError::InvalidPattern(pattern) => write!(f, "invalid pattern: {}", pattern),
+            PatternParseError::UnknownToken(token) => write!(f, "unknown pattern token: {}", token),
+            PatternParseError::UnknownProperty(property) => {
+                write!(f, "unknown block property: {}", property)
+            }
+            PatternParseError::EmptyClipboard => write!(f, "your clipboard is empty"),
+            PatternParseError::ZeroWeight => {
+                write!(
+                    f,
+                    "at least one part of the pattern needs a weight above 0%"
+                )
+            }
+            PatternParseError::MissingMapping(part) => {
+                write!(f, "expected from=to but found: {}", part)
+            }
+        }
+    }
+}
+
+pub type PatternParseResult<T> = std::result::Result<T, PatternParseError>;
+
+/// Parses the from=to pairs of `//replace`, like `glass=quartz_block,red_wool=lime_wool`.
+/// Parts without a `=` belong to the pattern before them, so `red_wool=50%lime_wool,glass`
+/// replaces red wool with a mix of lime wool and glass.
+fn parse_replace_mappings(
+    mappings_str: &str,
+    clipboard: Option<&Arc<WorldEditClipboard>>,
+) -> PatternParseResult<Vec<(WorldEditPattern, WorldEditPattern)>> {
+    let mut mappings: Vec<(&str, String)> = Vec::new();
+    for part in mappings_str.split(',') {
+        // The `=` has to be outside of block properties and can't be the one that starts a
+        // block id like `=5`
+        let mut depth = 0;
+        let separator = part.char_indices().find(|&(idx, c)| {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ => {}
+            }
+            c == '=' && depth == 0 && idx > 0
+        });
+        match separator {
+            Some((idx, _)) => mappings.push((&part[..idx], part[idx + 1..].to_owned())),
+            None => match mappings.last_mut() {
+                Some((_, pattern)) => {
+                    pattern.push(',');
+                    pattern.push_str(part);
+                }
+                None => return Err(PatternParseError::MissingMapping(part.to_owned())),
+            },
+        }
+    }
+    mappings
+        .into_iter()
+        .map(|(mask, pattern)| {
+            Ok((
+                WorldEditPattern::mask_from_str(mask)?,
+                WorldEditPattern::from_str_with_clipboard(&pattern, clipboard)?,
+            ))
+        })
+        .collect()
+}
+
+/// Matches `name` against `pattern`, where each `*` in the pattern matches any sequence of
+/// characters.
+fn wildcard_matches(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let name = match name.strip_prefix(prefix) {
+                Some(name) => name,
+                None => return false,
+            };
+            (0..=name.len())
+                .filter(|&i| name.is_char_boundary(i))
+                .any(|i| wildcard_matches(rest, &name[i..]))
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WorldEditPattern {
+    pub parts: Vec<WorldEditPatternPart>,
+    /// Masks starting with `!` match the blocks that none of the parts match
+    pub negated: bool,
+}
+
+impl WorldEditPattern {
+    /// Parses a pattern that is used as a mask, which may be negated with a leading `!`.
+    pub fn mask_from_str(mask_str: &str) -> PatternParseResult<WorldEditPattern> {
+        match mask_str.strip_prefix('!') {
+            Some(mask_str) => {
+                let mut mask = WorldEditPattern::from_str(mask_str)?;
+                mask.negated = true;
+                Ok(mask)
+            }
+            None => WorldEditPattern::from_str(mask_str),
+        }
+    }
+
+    pub fn from_str(pattern_str: &str) -> PatternParseResult<WorldEditPattern> {
+        WorldEditPattern::from_str_with_clipboard(pattern_str, None)
+    }
+
+    /// Parses a pattern which may use the `clipboard` for the `#clipboard` token.
+    pub fn from_str_with_clipboard(
+        pattern_str: &str,
+        clipboard: Option<&Arc<WorldEditClipboard>>,
+    ) -> PatternParseResult<WorldEditPattern> {
+        let mut pattern = WorldEditPattern {
+            parts: Vec::new(),
+            negated: false,
+        };
+        for part in pattern_str.split(',') {
+            lazy_static! {
+                static ref TOKEN_RE: Regex =
+                    Regex::new(r"^(([0-9]+(\.[0-9]+)?)%)?#([a-z]+)$").unwrap();
+            }
+            if let Some(token_match) = TOKEN_RE.captures(part) {
+                let weight = token_match
+                    .get(2)
+                    .map_or("100", |m| m.as_str())
+                    .parse::<f32>()
+                    .unwrap()
+                    / 100.0;
+                let part = match &token_match[4] {
+                    "clipboard" | "copy" => WorldEditPatternPart::Clipboard {
+                        weight,
+                        // A clipboard without any blocks has nothing to repeat
+                        clipboard: clipboard
+                            .filter(|cb| cb.size_x > 0 && cb.size_y > 0 && cb.size_z > 0)
+                            .ok_or(PatternParseError::EmptyClipboard)?
+                            .clone(),
+                    },
+                    _ => return Err(PatternParseError::UnknownToken(part.to_owned())),
+                };
+                pattern.parts.push(part);
+                continue;
+            }
+
+            lazy_static! {
+                static ref RE: Regex = Regex::new(r"^(([0-9]+(\.[0-9]+)?)%)?(=)?([0-9]+|((?:##)?(?:minecraft:)?)[a-zA-Z_*]+)(:([0-9]+)|\[(([a-zA-Z_]+=[a-zA-Z0-9*]+,?)+?)\])?((\|([^|]*?)){1,4})?$").unwrap();
+            }
+            let pattern_match = RE
+                .captures(part)
+                .ok_or_else(|| PatternParseError::InvalidPattern(part.to_owned()))?;
+
+            let mut blocks = if pattern_match.get(4).is_some() {
+                vec![Block::from_id(
+                    pattern_match
+                        .get(5)
+                        .map_or("0", |m| m.as_str())
+                        .parse::<u32>()
+                        .unwrap(),
+                )]
+            } else {
+                let block_name = pattern_match.get(5).unwrap().as_str();
+                if let Some(tag) = block_name.strip_prefix("##") {
+                    Block::from_tag(tag)
+                        .ok_or_else(|| PatternParseError::UnknownTag(part.to_owned()))?
+                } else if block_name.contains('*') {
+                    let block_name = block_name.trim_start_matches("minecraft:");
+                    let mut blocks: Vec<Block> = Vec::new();
+                    for name in Block::NAMES {
+                        if wildcard_matches(block_name, name) {
+                            let block = Block::from_name(name).unwrap();
+                            if !blocks.contains(&block) {
+                                blocks.push(block);
+                            }
+                        }
+                    }
+                    if blocks.is_empty() {
+                        return Err(PatternParseError::UnknownBlock(part.to_owned()));
+                    }
+                    blocks
+                } else {
+                    let block_name = block_name.trim_start_matches("minecraft:");
+                    vec![Block::from_name(block_name)
+                        .ok_or_else(|| PatternParseError::UnknownBlock(part.to_owned()))?]
+                }
+            };
+
+            if let Some(properties) = pattern_match.get(9) {
+                for property in properties.as_str().split(',').filter(|p| !p.is_empty()) {
+                    let (key, val) = property.split_once('=').unwrap();
+                    if val != "*" {
+                        for block in &mut blocks {
+                            block.set_property(key, val);
+                        }
+                        continue;
+                    }
+                    let mut expanded = Vec::new();
+                    for block in blocks {
+                        let values = block.property_values(key);
+                        if values.is_empty() {
+                            return Err(PatternParseError::UnknownProperty(property.to_owned()));
+                        }
+                        for value in values {
+                            let mut block = block;
+                            block.set_property(key, value);
+                            expanded.push(block);
+                        }
+                    }
+                    blocks = expanded;
+                }
+            }
+
+            let weight = pattern_match
+                .get(2)
+                .map_or("100", |m| m.as_str())
+                .parse::<f32>()
+                .unwrap()
+                / 100.0;
+
+            // Ids and properties like `repeater[delay=2]` only match that exact state
+            let exact_state = pattern_match.get(4).is_some() || pattern_match.get(7).is_some();
+            // A wildcard or tag splits the weight of its part evenly between all the blocks it matches
+            let block_weight = weight / blocks.len() as f32;
+            for block in blocks {
+                let block_id = block.get_id();
+                pattern.parts.push(WorldEditPatternPart::Block {
+                    weight: block_weight,
+                    block_id,
+                    matching_ids: if exact_state {
+                        block_id..=block_id
+                    } else {
+                        block.state_ids()
+                    },
+                });
+            }
+        }
+
+        if pattern.parts.iter().map(|part| part.weight()).sum::<f32>() <= 0.0 {
+            return Err(PatternParseError::ZeroWeight);
+        }
+        Ok(pattern)
+    }
+
+    pub fn matches(&self, block: Block) -> bool {
+        let block_id = block.get_id();
+        let matches_part = self.parts.iter().any(|part| match part {
+            WorldEditPatternPart::Block { matching_ids, .. } => matching_ids.contains(&block_id),
+            WorldEditPatternPart::Clipboard { .. } => false,
+        });
+        matches_part != self.negated
+    }
+
+    /// Returns the block id of patterns that always pick the same block
+    fn single_block_id(&self) -> Option<u32> {
+        match self.parts.as_slice() {
+            [WorldEditPatternPart::Block { block_id, .. }] => Some(*block_id),
+            _ => None,
+        }
+    }
+
+    /// Picks the block to place at `pos`. Most parts of a pattern don't depend on the position.
+    pub fn pick_at(&self, pos: BlockPos) -> Block {
+        let mut weight_sum = 0.0;
+        for part in &self.parts {
+            weight_sum += part.weight();
+        }
+
+        // Parsing makes sure that the sum is positive, but an empty range would panic
+        if weight_sum <= 0.0 {
+            return Block::Air {};
+        }
+        let mut rng = rand::thread_rng();
+        let mut random = rng.gen_range(0.0..weight_sum);
+
+        for part in &self.parts {
+            random -= part.weight();
+            if random <= 0.0 {
+                return part.pick_at(pos);
+            }
+        }
+
+        Block::Air {}
+    }
+}
+
+struct ChunkChangedRecord {
+    chunk_x: i32,
+    chunk_z: i32,
+    block_count: usize,
+}
+
+/// Operations that change at most this many blocks only send the changed blocks instead of
+/// resending the whole chunks.
+const MULTI_BLOCK_CHANGE_LIMIT: usize = 4096;
+
+struct WorldEditOperation {
+    pub records: Vec<ChunkChangedRecord>,
+    /// The blocks that were changed, until there are more than `MULTI_BLOCK_CHANGE_LIMIT`
+    changed_blocks: Vec<BlockPos>,
+    /// Whether blocks were changed without recording their positions, which means the
+    /// changed chunks have to be resent
+    resend_chunks: bool,
+    x_range: RangeInclusive<i32>,
+    y_range: RangeInclusive<i32>,
+    z_range: RangeInclusive<i32>,
+}
+
+impl WorldEditOperation {
+    fn new(first_pos: BlockPos, second_pos: BlockPos) -> WorldEditOperation {
+        let start_pos = first_pos.min(second_pos);
+        let end_pos = first_pos.max(second_pos);
+
+        let mut records: Vec<ChunkChangedRecord> = Vec::new();
+
+        for chunk_x in (start_pos.x >> 4)..=(end_pos.x >> 4) {
+            for chunk_z in (start_pos.z >> 4)..=(end_pos.z >> 4) {
+                records.push(ChunkChangedRecord {
+                    chunk_x,
+                    chunk_z,
+                    block_count: 0,
+                });
+            }
+        }
+
+        let x_range = start_pos.x..=end_pos.x;
+        let y_range = start_pos.y..=end_pos.y;
+        let z_range = start_pos.z..=end_pos.z;
+        WorldEditOperation {
+            records,
+            changed_blocks: Vec::new(),
+            resend_chunks: false,
+            x_range,
+            y_range,
+            z_range,
+        }
+    }
+
+    fn update_block(&mut self, block_pos: BlockPos) {
+        let chunk_x = block_pos.x >> 4;
+        let chunk_z = block_pos.z >> 4;
+
+        if let Some(packet) = self
+            .records
+            .iter_mut()
+            .find(|c| c.chunk_x == chunk_x && c.chunk_z == chunk_z)
+        {
+            packet.block_count += 1;
+        }
+        if self.changed_blocks.len() <= MULTI_BLOCK_CHANGE_LIMIT {
+            self.changed_blocks.push(block_pos);
+        }
+    }
+
+    /// Records `count` changed blocks in a chunk without their positions
+    fn update_chunk(&mut self, chunk_x: i32, chunk_z: i32, count: usize) {
+        if count == 0 {
+            return;
+        }
+        if let Some(record) = self
+            .records
+            .iter_mut()
+            .find(|c| c.chunk_x == chunk_x && c.chunk_z == chunk_z)
+        {
+            record.block_count += count;
+        }
+        self.resend_chunks = true;
+    }
+
+    fn blocks_updated(&self) -> usize {
+        let mut blocks_updated = 0;
+
+        for record in &self.records {
+            blocks_updated += record.block_count;
+        }
+
+        blocks_updated
+    }
+
+    fn x_range(&self) -> RangeInclusive<i32> {
+        self.x_range.to_owned()
+    }
+    fn y_range(&self) -> RangeInclusive<i32> {
+        self.y_range.to_owned()
+    }
+    fn z_range(&self) -> RangeInclusive<i32> {
+        self.z_range.to_owned()
+    }
+
+    fn range_len(range: &RangeInclusive<i32>) -> usize {
+        (range.end() - range.start()) as usize + 1
+    }
+
+    /// The total number of blocks in the operation's region
+    fn volume(&self) -> usize {
+        WorldEditOperation::range_len(&self.x_range)
+            * WorldEditOperation::range_len(&self.y_range)
+            * WorldEditOperation::range_len(&self.z_range)
+    }
+
+    /// Returns the position of the `idx`th block of the region, in the same x-y-z order the
+    /// range loops use.
+    fn pos_at(&self, idx: usize) -> BlockPos {
+        let size_y = WorldEditOperation::range_len(&self.y_range);
+        let size_z = WorldEditOperation::range_len(&self.z_range);
+        BlockPos::new(
+            self.x_range.start() + (idx / (size_y * size_z)) as i32,
+            self.y_range.start() + (idx / size_z % size_y) as i32,
+            self.z_range.start() + (idx % size_z) as i32,
+        )
+    }
+}
+
+fn worldedit_send_operation(plot: &mut Plot, operation: WorldEditOperation) {
+    if CONFIG.worldedit_effects && operation.blocks_updated() > 0 {
+        worldedit_play_effects(plot, &operation);
+    }
+    if !operation.resend_chunks && operation.changed_blocks.len() <= MULTI_BLOCK_CHANGE_LIMIT {
+        worldedit_send_changed_blocks(plot, &operation.changed_blocks);
+        return;
+    }
+    let chunks = operation
+        .records
+        .iter()
+        .map(|record| (record.chunk_x, record.chunk_z));
+    worldedit_send_chunks(plot, chunks, false);
+}
+
+/// Groups changed blocks by the 16x16x16 chunk section they're in, since a multi block change
+/// packet can only hold the blocks of one section. The positions in the records are relative
+/// to the section.
+fn multi_block_change_packets(
+    changes: impl IntoIterator<Item = (BlockPos, u32)>,
+) -> Vec<C3BMultiBlockChange> {
+    let mut sections: BTreeMap<(i32, i32, u32), Vec<C3BMultiBlockChangeRecord>> = BTreeMap::new();
+    for (pos, block_id) in changes {
+        let section = (pos.x >> 4, pos.z >> 4, (pos.y >> 4) as u32);
+        sections
+            .entry(section)
+            .or_default()
+            .push(C3BMultiBlockChangeRecord {
+                x: (pos.x & 0xF) as u8,
+                y: (pos.y & 0xF) as u8,
+                z: (pos.z & 0xF) as u8,
+                block_id,
+            });
+    }
+    sections
+        .into_iter()
+        .map(
+            |((chunk_x, chunk_z, chunk_y), records)| C3BMultiBlockChange {
+                chunk_x,
+                chunk_z,
+                chunk_y,
+                records,
+            },
+        )
+        .collect()
+}
+
+/// Sends the current state of the changed blocks to everyone on the plot. Unlike resending the
+/// chunks this doesn't include the block entities, so those are sent separately.
+fn worldedit_send_changed_blocks(plot: &mut Plot, changed_blocks: &[BlockPos]) {
+    let changes = changed_blocks
+        .iter()
+        .map(|&pos| (pos, plot.get_block_raw(pos)));
+    let mut batch = PacketBatch::new();
+    for packet in multi_block_change_packets(changes) {
+        batch.push(packet.encode());
+    }
+    for player in &mut plot.players {
+        player.client.send_batch(&mut batch);
+    }
+    for &pos in changed_blocks {
+        if let Some(block_entity) = plot.get_block_entity(pos).cloned() {
+            plot.send_block_entity_data(pos, &block_entity);
+        }
+    }
+}
+
+/// Resends chunks to everyone on the plot after they were changed. The light is sent along
+/// with them, otherwise the changed chunks render dark until the player rejoins. All the
+/// packets are batched together so that they're only encoded and compressed once.
+/// Only full chunks include the biomes.
+fn worldedit_send_chunks(
+    plot: &mut Plot,
+    chunks: impl IntoIterator<Item = (i32, i32)>,
+    full_chunk: bool,
+) {
+    let mut batch = PacketBatch::new();
+    for (chunk_x, chunk_z) in chunks {
+        if let Some(chunk) = plot.get_chunk(chunk_x, chunk_z) {
+            batch.push(chunk.encode_packet(full_chunk));
+            batch.push(chunk.encode_light_packet());
+        }
+    }
+    for player in &mut plot.players {
+        player.client.send_batch(&mut batch);
+    }
+}
+
+/// Plays the block break effect at the corners of the operation's region so that everyone on
+/// the plot can see that something happened. Only the corners are used so that large operations
+/// don't flood the clients with effects.
+fn worldedit_play_effects(plot: &mut Plot, operation: &WorldEditOperation) {
+    // Block break particles and sound, the data is the block state id
+    const BLOCK_BREAK_EFFECT: i32 = 2001;
+
+    let mut corners = Vec::with_capacity(8);
+    for x in [*operation.x_range.start(), *operation.x_range.end()].iter() {
+        for y in [*operation.y_range.start(), *operation.y_range.end()].iter() {
+            for z in [*operation.z_range.start(), *operation.z_range.end()].iter() {
+                let pos = BlockPos::new(*x, *y, *z);
+                if !corners.contains(&pos) {
+                    corners.push(pos);
+                }
+            }
+        }
+    }
+
+    for pos in corners {
+        let block_id = plot.get_block_raw(pos);
+        if block_id == 0 {
+            continue;
+        }
+        let effect = C21Effect {
+            effect_id: BLOCK_BREAK_EFFECT,
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            data: block_id as i32,
+            disable_relative_volume: false,
+        }
+        .encode();
+        for player in &mut plot.players {
+            player.client.send_packet(&effect);
+        }
+    }
+}
+
+fn worldedit_start_operation(plot: &mut Plot, player: usize) -> WorldEditOperation {
+    let player = &mut plot.players[player];
+    let first_pos = player.first_position.unwrap();
+    let second_pos = player.second_position.unwrap();
+    WorldEditOperation::new(first_pos, second_pos)
+}
+
+/// Operations touching more blocks than this are queued on the plot instead of being run
+/// all at once, so that they don't freeze the plot while they run.
+const QUEUE_THRESHOLD: usize = 1_000_000;
+/// The amount of blocks a queued operation may process per plot update.
+const QUEUE_BLOCKS_PER_UPDATE: usize = 200_000;
+/// Operations that run right away check if they went over `worldedit_time_budget_ms` every
+/// time they have processed this many blocks.
+const TIME_BUDGET_CHECK_BLOCKS: usize = 10_000;
+
+/// How long each phase of an operation took, shown to players who use the `-t` flag.
+struct Timings {
+    phases: Vec<(&'static str, Duration)>,
+    phase_start: Instant,
+}
+
+impl Timings {
+    fn new() -> Timings {
+        Timings {
+            phases: Vec::new(),
+            phase_start: Instant::now(),
+        }
+    }
+
+    /// Ends the current phase. Queued jobs run over several updates, so the time of a phase
+    /// that already ended before is added to it.
+    fn end_phase(&mut self, name: &'static str) {
+        let elapsed = self.phase_start.elapsed();
+        match self.phases.iter_mut().find(|(phase, _)| *phase == name) {
+            Some((_, duration)) => *duration += elapsed,
+            None => self.phases.push((name, elapsed)),
+        }
+        self.phase_start = Instant::now();
+    }
+
+    /// Starts a new phase without counting the time since the last one, like the time a
+    /// queued job spent waiting for its next update.
+    fn resume(&mut self) {
+        self.phase_start = Instant::now();
+    }
+
+    fn summary(&self) -> String {
+        let phases: Vec<String> = self
+            .phases
+            .iter()
+            .map(|(phase, duration)| format!("{}: {:?}", phase, duration))
+            .collect();
+        format!("Timings: {}", phases.join(", "))
+    }
+}
+
+enum WorldEditJobKind {
+    Set {
+        pattern: WorldEditPattern,
+        /// The corners of the chunk sections that were already filled, see `fill_sections`
+        filled: Option<(BlockPos, BlockPos)>,
+    },
+    Replace {
+        /// Pairs of masks and patterns. A block is replaced using the first mask it matches.
+        mappings: Vec<(WorldEditPattern, WorldEditPattern)>,
+        /// Keep the block entities of replaced blocks if they fit the new block
+        keep_block_entities: bool,
+        /// Copy the properties of replaced blocks to the new block where it has them too
+        keep_properties: bool,
+    },
+    Paste {
+        /// Shared with the player's clipboard, which may be replaced while the job runs
+        clipboard: Arc<WorldEditClipboard>,
+        /// Where the first block of the clipboard ends up
+        origin: BlockPos,
+        ignore_air: bool,
+        /// Only the blocks of the clipboard matching this are pasted
+        mask: Option<WorldEditPattern>,
+    },
+    /// Walks through a `Set` or `Replace` without changing anything, counting the blocks it
+    /// would change by what they are now
+    DryRun {
+        kind: Box<WorldEditJobKind>,
+        blocks_changed: usize,
+        counts: HashMap<u32, usize>,
+    },
+}
+
+/// A worldedit operation which can be processed in several steps.
+pub struct WorldEditJob {
+    /// The uuid of the player who started the job. An index can't be used here
+    /// since players can join or leave the plot while the job is running.
+    player_uuid: u128,
+    kind: WorldEditJobKind,
+    operation: WorldEditOperation,
+    /// The index of the next block to process
+    idx: usize,
+    total: usize,
+    /// The last progress percentage that was reported to the player
+    reported_progress: usize,
+    /// Whether the blocks in the region should get block updates once the job is done
+    update_blocks: bool,
+    /// The uuid of the boss bar showing the progress of the job, if it was queued
+    boss_bar: Option<u128>,
+    start_time: Instant,
+    /// The blocks changed so far, if the job records its own undo instead of a snapshot
+    /// being taken before it started
+    undo: Option<Vec<UndoBlockChange>>,
+    timings: Timings,
+    /// Whether the timings are shown to the player once the job is done
+    show_timings: bool,
+}
+
+impl WorldEditJob {
+    fn new(
+        player_uuid: u128,
+        kind: WorldEditJobKind,
+        operation: WorldEditOperation,
+        start_time: Instant,
+    ) -> WorldEditJob {
+        let total = operation.volume();
+        WorldEditJob {
+            player_uuid,
+            kind,
+            operation,
+            idx: 0,
+            total,
+            reported_progress: 0,
+            update_blocks: false,
+            boss_bar: None,
+            start_time,
+            undo: None,
+            timings: Timings::new(),
+            show_timings: false,
+        }
+    }
+
+    /// Processes up to `max_blocks` blocks. Returns true if the job is done.
+    fn run(&mut self, plot: &mut Plot, max_blocks: usize) -> bool {
+        self.timings.resume();
+        let end = self.total.min(self.idx.saturating_add(max_blocks));
+        for idx in self.idx..end {
+            let block_pos = self.operation.pos_at(idx);
+            let changed = match &mut self.kind {
+                WorldEditJobKind::Set { pattern, filled } => {
+                    if let Some((start, end)) = filled {
+                        if block_pos.min(*start) == *start && block_pos.max(*end) == *end {
+                            continue;
+                        }
+                    }
+                    set_block_clearing_entity(plot, block_pos, pattern.pick_at(block_pos).get_id())
+                }
+                WorldEditJobKind::Replace {
+                    mappings,
+                    keep_block_entities,
+                    keep_properties,
+                } => {
+                    let old_block = plot.get_block(block_pos);
+                    let block =
+                        match pick_replacement(mappings, block_pos, old_block, *keep_properties) {
+                            Some(block) => block,
+                            None => continue,
+                        };
+                    let keep = *keep_block_entities
+                        && plot
+                            .get_block_entity(block_pos)
+                            .is_some_and(|block_entity| block_entity.fits_block(block));
+                    let old_block_entity = if keep {
+                        None
+                    } else {
+                        plot.get_block_entity(block_pos).cloned()
+                    };
+                    if old_block_entity.is_some() {
+                        plot.delete_block_entity(block_pos);
+                    }
+                    let changed = plot.set_block_raw(block_pos, block.get_id());
+                    if changed || old_block_entity.is_some() {
+                        if let Some(undo) = &mut self.undo {
+                            undo.push(UndoBlockChange {
+                                pos: block_pos,
+                                block_id: old_block.get_id(),
+                                block_entity: old_block_entity,
+                            });
+                        }
+                    }
+                    changed
+                }
+                WorldEditJobKind::Paste {
+                    clipboard,
+                    origin,
+                    ignore_air,
+                    mask,
+                } => {
+                    let relative = block_pos - *origin;
+                    let entry = clipboard
+                        .data
+                        .get_entry(clipboard.index(relative.x, relative.y, relative.z));
+                    if *ignore_air && entry == 0 {
+                        continue;
+                    }
+                    if let Some(mask) = mask {
+                        if !mask.matches(Block::from_id(entry)) {
+                            continue;
+                        }
+                    }
+                    plot.set_block_raw(block_pos, entry)
+                }
+                WorldEditJobKind::DryRun {
+                    kind,
+                    blocks_changed,
+                    counts,
+                } => {
+                    let old_block = plot.get_block(block_pos);
+                    if dry_run_block(kind, block_pos, old_block)
+                        .is_some_and(|block| block != old_block)
+                    {
+                        *blocks_changed += 1;
+                        *counts.entry(old_block.get_id()).or_default() += 1;
+                    }
+                    continue;
+                }
+            };
+            if changed {
+                self.operation.update_block(block_pos);
+            }
+        }
+        self.idx = end;
+        self.timings.end_phase("block writes");
+        self.idx >= self.total
+    }
+
+    fn get_player<'a>(&self, plot: &'a mut Plot) -> Option<&'a mut Player> {
+        plot.players.iter_mut().find(|p| p.uuid == self.player_uuid)
+    }
+
+    fn send_boss_bar(&mut self, plot: &mut Plot, action: C0CBossBarAction) {
+        if let Some(uuid) = self.boss_bar {
+            let packet = C0CBossBar { uuid, action }.encode();
+            if let Some(player) = self.get_player(plot) {
+                player.client.send_packet(&packet);
+            }
+        }
+    }
+
+    /// Shows a boss bar to the player which fills up as the job runs.
+    fn show_boss_bar(&mut self, plot: &mut Plot) {
+        self.boss_bar = Some(rand::random());
+        let title = json!({
+            "text": "Worldedit operation",
+            "color": "light_purple"
+        })
+        .to_string();
+        self.send_boss_bar(
+            plot,
+            C0CBossBarAction::Add {
+                title,
+                health: 0.0,
+                // Purple, without any divisions
+                color: 5,
+                division: 0,
+                flags: 0,
+            },
+        );
+    }
+
+    /// Updates the boss bar and lets the player know how far along the job is every 10 percent.
+    fn report_progress(&mut self, plot: &mut Plot) {
+        self.send_boss_bar(
+            plot,
+            C0CBossBarAction::UpdateHealth(self.idx as f32 / self.total as f32),
+        );
+        let progress = self.idx * 100 / self.total;
+        if progress / 10 <= self.reported_progress / 10 {
+            return;
+        }
+        self.reported_progress = progress;
+        let title = json!({
+            "text": format!("Worldedit operation ({}%)", progress),
+            "color": "light_purple"
+        })
+        .to_string();
+        self.send_boss_bar(plot, C0CBossBarAction::UpdateTitle(title));
+        let message = format!(
+            "{}% complete, {} of {} block(s) processed",
+            progress, self.idx, self.total
+        );
+        if let Some(player) = self.get_player(plot) {
+            player.send_worldedit_message(&message);
+        }
+    }
+
+    fn finish(mut self, plot: &mut Plot) {
+        self.send_boss_bar(plot, C0CBossBarAction::Remove);
+        if let WorldEditJobKind::DryRun {
+            blocks_changed,
+            counts,
+            ..
+        } = &mut self.kind
+        {
+            let (blocks_changed, counts) = (*blocks_changed, std::mem::take(counts));
+            let elapsed = self.start_time.elapsed();
+            if let Some(player) = self.get_player(plot) {
+                player.send_worldedit_message(&format!(
+                    "Dry run: {} block(s) would be changed ({:?})",
+                    blocks_changed, elapsed
+                ));
+                send_block_counts(player, counts, blocks_changed);
+            }
+            return;
+        }
+        if let Some(changes) = self.undo.take() {
+            let undo = WorldEditUndo {
+                changes: WorldEditUndoChanges::Blocks(changes),
+                plot_x: plot.x,
+                plot_z: plot.z,
+            };
+            if let Some(player) = self.get_player(plot) {
+                player.worldedit_undo.push(undo);
+            }
+        }
+        if self.update_blocks {
+            // The blocks around the region are updated too since they may depend on what was
+            // changed, as long as they're still in the plot
+            let operation = &self.operation;
+            let start = BlockPos::new(
+                operation.x_range.start() - 1,
+                operation.y_range.start() - 1,
+                operation.z_range.start() - 1,
+            );
+            let end = BlockPos::new(
+                operation.x_range.end() + 1,
+                operation.y_range.end() + 1,
+                operation.z_range.end() + 1,
+            );
+            let (start, end) = (clamp_to_plot(plot, start), clamp_to_plot(plot, end));
+            for x in start.x..=end.x {
+                for y in start.y..=end.y {
+                    for z in start.z..=end.z {
+                        let pos = BlockPos::new(x, y, z);
+                        plot.get_block(pos).update(plot, pos);
+                    }
+                }
+            }
+            self.timings.end_phase("block updates");
+        }
+        // Block entities are stored before the chunks get sent so that they're part of the chunk data
+        let mut block_entities = Vec::new();
+        if let WorldEditJobKind::Paste {
+            clipboard,
+            origin,
+            mask,
+            ..
+        } = &self.kind
+        {
+            for (pos, block_entity) in &clipboard.block_entities {
+                if !clipboard.contains(*pos) {
+                    continue;
+                }
+                if let Some(mask) = mask {
+                    let entry = clipboard
+                        .data
+                        .get_entry(clipboard.index(pos.x, pos.y, pos.z));
+                    if !mask.matches(Block::from_id(entry)) {
+                        continue;
+                    }
+                }
+                let pos = *origin + *pos;
+                if let Some(chunk) = plot.get_chunk_mut(pos.x >> 4, pos.z >> 4) {
+                    chunk.set_block_entity(
+                        BlockPos::new(pos.x & 0xF, pos.y, pos.z & 0xF),
+                        block_entity.clone(),
+                    );
+                }
+                block_entities.push((pos, block_entity.clone()));
+            }
+        }
+        // Resent chunks contain all of their block entities and changed blocks are sent along
+        // with theirs, so only the block entities of blocks that didn't change are left to send
+        let resends_chunks = self.operation.resend_chunks
+            || self.operation.changed_blocks.len() > MULTI_BLOCK_CHANGE_LIMIT;
+        if resends_chunks {
+            block_entities.clear();
+        } else {
+            let changed: HashSet<BlockPos> =
+                self.operation.changed_blocks.iter().copied().collect();
+            block_entities.retain(|(pos, _)| !changed.contains(pos));
+        }
+        let blocks_updated = self.operation.blocks_updated();
+        let elapsed = self.start_time.elapsed();
+        // Large operations can be shown as a title so that their completion is hard to miss
+        let show_title = CONFIG.worldedit_titles && self.total > QUEUE_THRESHOLD;
+        let was_queued = self.boss_bar.is_some();
+        if let Some(player) = self.get_player(plot) {
+            // Queued jobs can take a while, so make some noise when they're done
+            if was_queued {
+                player.play_sound("minecraft:block.note_block.bell", 1.0, 1.0);
+            }
+            if show_title {
+                player.send_worldedit_title(
+                    "Operation completed",
+                    &format!("{} block(s) affected ({:?})", blocks_updated, elapsed),
+                );
+            } else {
+                player.send_worldedit_message(&format!(
+                    "Operation completed: {} block(s) affected ({:?})",
+                    blocks_updated, elapsed
+                ));
+            }
+        }
+        worldedit_send_operation(plot, self.operation);
+        for (pos, block_entity) in &block_entities {
+            plot.send_block_entity_data(*pos, block_entity);
+        }
+        self.timings.end_phase("sending chunks");
+        if self.show_timings {
+            let summary = self.timings.summary();
+            let uuid = self.player_uuid;
+            if let Some(player) = plot.players.iter_mut().find(|p| p.uuid == uuid) {
+                player.send_worldedit_message(&summary);
+            }
+        }
+    }
+}
+
+/// Sets a block and removes the block entity that was there, since it belonged to the old
+/// block. Returns true if anything was changed.
+fn set_block_clearing_entity(world: &mut impl World, pos: BlockPos, block_id: u32) -> bool {
+    let had_block_entity = world.get_block_entity(pos).is_some();
+    if had_block_entity {
+        world.delete_block_entity(pos);
+    }
+    world.set_block_raw(pos, block_id) || had_block_entity
+}
+
+/// Runs the job right away if it's small enough, otherwise it gets queued on the plot.
+fn worldedit_run_job(plot: &mut Plot, player: usize, mut job: WorldEditJob) {
+    // Anything already queued must finish first or it would overwrite this job's changes
+    if job.total <= QUEUE_THRESHOLD && plot.worldedit_queue.is_empty() {
+        // Operations that take too long are moved to the queue so the plot keeps responding
+        let budget = Duration::from_millis(CONFIG.worldedit_time_budget_ms.max(0) as u64);
+        loop {
+            if job.run(plot, TIME_BUDGET_CHECK_BLOCKS) {
+                job.finish(plot);
+                return;
+            }
+            if job.start_time.elapsed() > budget {
+                break;
+            }
+        }
+        plot.players[player].send_worldedit_message(&format!(
+            "Operation is taking a while: the remaining {} block(s) will be processed over time.",
+            job.total - job.idx
+        ));
+    } else {
+        plot.players[player].send_worldedit_message(&format!(
+            "Operation queued: {} block(s) will be processed over time.",
+            job.total
+        ));
+    }
+    job.show_boss_bar(plot);
+    // A job that went over the time budget already made some progress, which the boss bar
+    // should show from the start
+    if job.idx > 0 {
+        job.report_progress(plot);
+    }
+    plot.worldedit_queue.push_back(job);
+}
+
+/// Processes a portion of the first job in the plot's worldedit queue. This gets called every plot update.
+pub fn process_queue(plot: &mut Plot) {
+    let mut job = match plot.worldedit_queue.pop_front() {
+        Some(job) => job,
+        None => return,
+    };
+    if job.run(plot, QUEUE_BLOCKS_PER_UPDATE) {
+        job.finish(plot);
+    } else {
+        job.report_progress(plot);
+        plot.worldedit_queue.push_front(job);
+    }
+}
+
+/// Finishes the queued jobs up to the last one started by the player with `uuid`. This is done
+/// before the player leaves the plot, since the undo of a job is given to its player once it's
+/// done.
+pub fn finish_jobs_of(plot: &mut Plot, uuid: u128) {
+    while plot
+        .worldedit_queue
+        .iter()
+        .any(|job| job.player_uuid == uuid)
+    {
+        finish_next_job(plot);
+    }
+}
+
+/// Finishes all queued jobs, so that the plot isn't saved with some of them half done.
+pub fn finish_queue(plot: &mut Plot) {
+    while !plot.worldedit_queue.is_empty() {
+        finish_next_job(plot);
+    }
+}
+
+fn finish_next_job(plot: &mut Plot) {
+    if let Some(mut job) = plot.worldedit_queue.pop_front() {
+        job.run(plot, usize::MAX);
+        job.finish(plot);
+    }
+}
+
+fn execute_set(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+    let pattern = ctx.arguments[0].unwrap_pattern().clone();
+
+    let mut operation = worldedit_start_operation(ctx.plot, ctx.player_idx);
+    if ctx.has_flag('n') {
+        let kind = WorldEditJobKind::Set {
+            pattern,
+            filled: None,
+        };
+        start_dry_run(&mut ctx, operation, start_time, kind);
+        return;
+    }
+    if !check_limit(&mut ctx, operation.volume()) {
+        return;
+    }
+    let mut timings = Timings::new();
+    capture_undo(
+        ctx.plot,
+        ctx.player_idx,
+        ctx.get_player().first_position.unwrap(),
+        ctx.get_player().second_position.unwrap(),
+    );
+    timings.end_phase("undo snapshot");
+
+    let filled = match pattern.single_block_id() {
+        Some(block_id) => {
+            let filled = fill_sections(ctx.plot, &mut operation, block_id);
+            timings.end_phase("section fills");
+            filled
+        }
+        _ => None,
+    };
+    let mut job = WorldEditJob::new(
+        ctx.get_player().uuid,
+        WorldEditJobKind::Set { pattern, filled },
+        operation,
+        start_time,
+    );
+    job.update_blocks = ctx.has_flag('u');
+    job.timings = timings;
+    job.show_timings = ctx.has_flag('t');
+    worldedit_run_job(ctx.plot, ctx.player_idx, job);
+}
+
+/// Fills the chunk sections that are completely inside of the operation with `block_id` at
+/// once, which is a lot faster than setting their blocks one by one. Returns the corners of
+/// the filled area, if any section was filled.
+fn fill_sections(
+    plot: &mut Plot,
+    operation: &mut WorldEditOperation,
+    block_id: u32,
+) -> Option<(BlockPos, BlockPos)> {
+    // The first and last sections that the operation covers completely
+    let first = |range: &RangeInclusive<i32>| (range.start() + 15) >> 4;
+    let last = |range: &RangeInclusive<i32>| ((range.end() + 1) >> 4) - 1;
+    let start = BlockPos::new(
+        first(&operation.x_range),
+        first(&operation.y_range),
+        first(&operation.z_range),
+    );
+    let end = BlockPos::new(
+        last(&operation.x_range),
+        last(&operation.y_range),
+        last(&operation.z_range),
+    );
+    if start.x > end.x || start.y > end.y || start.z > end.z {
+        return None;
+    }
+    for chunk_x in start.x..=end.x {
+        for chunk_z in start.z..=end.z {
+            let chunk = match plot.get_chunk_mut(chunk_x, chunk_z) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+            let mut changed = 0;
+            for section_y in start.y..=end.y {
+                changed += chunk.fill_section(section_y as u8, block_id);
+            }
+            operation.update_chunk(chunk_x, chunk_z, changed);
+        }
+    }
+    Some((
+        BlockPos::new(start.x << 4, start.y << 4, start.z << 4),
+        BlockPos::new((end.x << 4) + 15, (end.y << 4) + 15, (end.z << 4) + 15),
+    ))
+}
+
+/// Picks the block that replaces `old_block` at `pos` from the first of the `mappings` whose
+/// mask matches it. Returns `None` if the block isn't replaced.
+fn pick_replacement(
+    mappings: &[(WorldEditPattern, WorldEditPattern)],
+    pos: BlockPos,
+    old_block: Block,
+    keep_properties: bool,
+) -> Option<Block> {
+    let (_, pattern) = mappings
+        .iter()
+        .find(|(filter, _)| filter.matches(old_block))?;
+    let mut block = pattern.pick_at(pos);
+    if keep_properties {
+        block.copy_properties(old_block);
+    }
+    Some(block)
+}
+
+fn execute_replace(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let from = ctx.arguments[0].unwrap_string();
+    let to = ctx.arguments[1].unwrap_string();
+    let clipboard = ctx.get_player().worldedit_clipboard.as_ref();
+    let mappings = if to.is_empty() {
+        parse_replace_mappings(from, clipboard)
+    } else {
+        WorldEditPattern::mask_from_str(from).and_then(|filter| {
+            let pattern = WorldEditPattern::from_str_with_clipboard(to, clipboard)?;
+            Ok(vec![(filter, pattern)])
+        })
+    };
+    let mappings = match mappings {
+        Ok(mappings) => mappings,
+        Err(err) => {
+            ctx.get_player_mut()
+                .send_error_message(&format!("Invalid replacement: {}", err));
+            return;
+        }
+    };
+
+    let operation = worldedit_start_operation(ctx.plot, ctx.player_idx);
+    let keep_properties = ctx.has_flag('p');
+    if ctx.has_flag('n') {
+        let kind = WorldEditJobKind::Replace {
+            mappings,
+            keep_block_entities: false,
+            keep_properties,
+        };
+        start_dry_run(&mut ctx, operation, start_time, kind);
+        return;
+    }
+    if !check_limit(&mut ctx, operation.volume()) {
+        return;
+    }
+    let update_blocks = ctx.has_flag('u');
+    // The snapshot would include the changes of a partly done job
+    if update_blocks && !check_queue_empty(&mut ctx) {
+        return;
+    }
+    let mut timings = Timings::new();
+    // Block updates can change blocks that weren't replaced, which only a snapshot can undo
+    if update_blocks {
+        capture_undo(
+            ctx.plot,
+            ctx.player_idx,
+            ctx.get_player().first_position.unwrap(),
+            ctx.get_player().second_position.unwrap(),
+        );
+        timings.end_phase("undo snapshot");
+    }
+
+    let mut job = WorldEditJob::new(
+        ctx.get_player().uuid,
+        WorldEditJobKind::Replace {
+            mappings,
+            keep_block_entities: ctx.has_flag('k'),
+            keep_properties,
+        },
+        operation,
+        start_time,
+    );
+    job.update_blocks = update_blocks;
+    if !update_blocks {
+        job.undo = Some(Vec::new());
+    }
+    job.timings = timings;
+    job.show_timings = ctx.has_flag('t');
+    worldedit_run_job(ctx.plot, ctx.player_idx, job);
+}
+
+fn execute_replacenear(ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let size = ctx.arguments[0].unwrap_uint() as i32;
+    let filter = ctx.arguments[1].unwrap_mask().clone();
+    let pattern = ctx.arguments[2].unwrap_pattern().clone();
+    replace_near(ctx, size, filter, pattern, start_time);
+}
+
+fn execute_removenear(ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let filter = ctx.arguments[0].unwrap_mask().clone();
+    let size = ctx.arguments[1].unwrap_uint() as i32;
+    let air = WorldEditPattern {
+        parts: vec![WorldEditPatternPart::Block {
+            weight: 1.0,
+            block_id: 0,
+            matching_ids: 0..=0,
+        }],
+        negated: false,
+    };
+    replace_near(ctx, size, filter, air, start_time);
+}
+
+/// Replaces the blocks matching `filter` within `size` blocks of the player with `pattern`.
+fn replace_near(
+    mut ctx: CommandExecuteContext<'_>,
+    size: i32,
+    filter: WorldEditPattern,
+    pattern: WorldEditPattern,
+    start_time: Instant,
+) {
+    let player = ctx.get_player();
+    let origin = BlockPos::new(
+        player.x.floor() as i32,
+        player.y.floor() as i32,
+        player.z.floor() as i32,
+    );
+    let first_pos = clamp_to_plot(ctx.plot, origin - BlockPos::new(size, size, size));
+    let second_pos = clamp_to_plot(ctx.plot, origin + BlockPos::new(size, size, size));
+
+    let operation = WorldEditOperation::new(first_pos, second_pos);
+    if !check_limit(&mut ctx, operation.volume()) {
+        return;
+    }
+
+    let mut job = WorldEditJob::new(
+        ctx.get_player().uuid,
+        WorldEditJobKind::Replace {
+            mappings: vec![(filter, pattern)],
+            keep_block_entities: false,
+            keep_properties: false,
+        },
+        operation,
+        start_time,
+    );
+    job.undo = Some(Vec::new());
+    job.show_timings = ctx.has_flag('t');
+    worldedit_run_job(ctx.plot, ctx.player_idx, job);
+}
+
+fn execute_count(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let filter = ctx.arguments[0].unwrap_mask().clone();
+
+    let mut blocks_counted = 0;
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    let mut count_block = |block: Block| {
+        if filter.matches(block) {
+            blocks_counted += 1;
+            *counts.entry(block.get_id()).or_default() += 1;
+        }
+    };
+    if ctx.has_flag('c') {
+        let clipboard = match &ctx.get_player().worldedit_clipboard {
+            Some(clipboard) => clipboard,
+            None => {
+                ctx.get_player_mut()
+                    .send_error_message("Your clipboard is empty. Use //copy first.");
+                return;
+            }
+        };
+        for i in 0..clipboard.data.entries() {
+            count_block(Block::from_id(clipboard.data.get_entry(i)));
+        }
+    } else {
+        if !check_selection(&mut ctx) {
+            return;
+        }
+        let operation = worldedit_start_operation(ctx.plot, ctx.player_idx);
+        for x in operation.x_range() {
+            for y in operation.y_range() {
+                for z in operation.z_range() {
+                    count_block(ctx.plot.get_block(BlockPos::new(x, y, z)));
+                }
+            }
+        }
+    }
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "Counted {} block(s) ({:?})",
+        blocks_counted,
+        start_time.elapsed()
+    ));
+
+    if ctx.has_flag('d') {
+        send_block_counts(ctx.get_player_mut(), counts, blocks_counted);
+    }
+}
+
+/// Sends how often each block was counted, out of `total` blocks.
+fn send_block_counts(player: &mut Player, counts: HashMap<u32, usize>, total: usize) {
+    let mut counts: Vec<(u32, usize)> = counts.into_iter().collect();
+    // Most common blocks first, ties are broken by id so the output is stable
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    for (block_id, count) in counts {
+        player.send_worldedit_message(&format!(
+            "{:>8} ({:.3}%) {:?}",
+            count,
+            count as f64 / total as f64 * 100.0,
+            Block::from_id(block_id)
+        ));
+    }
+}
+
+/// Starts a job that walks through the operation without changing anything, and tells the
+/// player how many blocks `kind` would change and which blocks they are now.
+fn start_dry_run(
+    ctx: &mut CommandExecuteContext<'_>,
+    operation: WorldEditOperation,
+    start_time: Instant,
+    kind: WorldEditJobKind,
+) {
+    let kind = WorldEditJobKind::DryRun {
+        kind: Box::new(kind),
+        blocks_changed: 0,
+        counts: HashMap::new(),
+    };
+    let job = WorldEditJob::new(ctx.get_player().uuid, kind, operation, start_time);
+    worldedit_run_job(ctx.plot, ctx.player_idx, job);
+}
+
+/// The block that a job of `kind` would put at `pos` instead of `old_block`, if any.
+fn dry_run_block(kind: &WorldEditJobKind, pos: BlockPos, old_block: Block) -> Option<Block> {
+    match kind {
+        WorldEditJobKind::Set { pattern, .. } => Some(pattern.pick_at(pos)),
+        WorldEditJobKind::Replace {
+            mappings,
+            keep_properties,
+            ..
+        } => pick_replacement(mappings, pos, old_block, *keep_properties),
+        WorldEditJobKind::Paste { .. } | WorldEditJobKind::DryRun { .. } => None,
+    }
+}
+
+fn execute_fill(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let pattern = ctx.arguments[0].unwrap_pattern().clone();
+    let radius = ctx.arguments[1].unwrap_uint() as i32;
+    let depth = ctx.arguments[2].unwrap_uint() as i32;
+    let limit = ctx.get_player().worldedit_limit;
+
+    let origin = BlockPos::new(
+        ctx.get_player().x.floor() as i32,
+        ctx.get_player().y.floor() as i32,
+        ctx.get_player().z.floor() as i32,
+    );
+
+    // Spread out through air from the origin. Filling never goes above where the player is standing.
+    let (plot_x, plot_z) = (ctx.plot.x, ctx.plot.z);
+    let can_fill = |plot: &Plot, pos: BlockPos| {
+        let dx = (pos.x - origin.x) as i64;
+        let dz = (pos.z - origin.z) as i64;
+        Plot::in_plot_bounds(plot_x, plot_z, pos.x, pos.z)
+            && pos.y >= 0
+            && pos.y <= origin.y
+            && pos.y > origin.y - depth
+            && dx * dx + dz * dz <= radius as i64 * radius as i64
+            && matches!(plot.get_block(pos), Block::Air {})
+    };
+
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    if can_fill(ctx.plot, origin) {
+        visited.insert(origin);
+        queue.push_back(origin);
+    }
+    let mut to_fill = Vec::new();
+    while let Some(pos) = queue.pop_front() {
+        if to_fill.len() >= limit {
+            ctx.get_player_mut().send_error_message(&format!(
+                "Fill would affect more than {} block(s), the limit.",
+                limit
+            ));
+            return;
+        }
+        to_fill.push(pos);
+        for facing in &[
+            BlockFacing::North,
+            BlockFacing::South,
+            BlockFacing::East,
+            BlockFacing::West,
+            BlockFacing::Down,
+        ] {
+            let next = facing.offset_pos(pos, 1);
+            if !visited.contains(&next) && can_fill(ctx.plot, next) {
+                visited.insert(next);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    if to_fill.is_empty() {
+        ctx.get_player_mut()
+            .send_worldedit_message("There is nothing to fill here.");
+        return;
+    }
+
+    let start_pos = to_fill.iter().fold(origin, |acc, pos| acc.min(*pos));
+    let end_pos = to_fill.iter().fold(origin, |acc, pos| acc.max(*pos));
+    capture_undo(ctx.plot, ctx.player_idx, start_pos, end_pos);
+
+    let mut operation = WorldEditOperation::new(start_pos, end_pos);
+    for pos in to_fill {
+        if ctx.plot.set_block_raw(pos, pattern.pick_at(pos).get_id()) {
+            operation.update_block(pos);
+        }
+    }
+
+    let blocks_updated = operation.blocks_updated();
+    worldedit_send_operation(ctx.plot, operation);
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "{} block(s) have been created. ({:?})",
+        blocks_updated,
+        start_time.elapsed()
+    ));
+}
+
+fn create_clipboard(
+    plot: &mut Plot,
+    origin: BlockPos,
+    first_pos: BlockPos,
+    second_pos: BlockPos,
+) -> WorldEditClipboard {
+    let start_pos = first_pos.min(second_pos);
+    let end_pos = first_pos.max(second_pos);
+    let size_x = (end_pos.x - start_pos.x) as u32 + 1;
+    let size_y = (end_pos.y - start_pos.y) as u32 + 1;
+    let size_z = (end_pos.z - start_pos.z) as u32 + 1;
+    let offset = origin - start_pos;
+    let mut cb = WorldEditClipboard {
+        offset_x: offset.x,
+        offset_y: offset.y,
+        offset_z: offset.z,
+        size_x,
+        size_y,
+        size_z,
+        data: PalettedBitBuffer::with_entries((size_x * size_y * size_z) as usize),
+        block_entities: HashMap::new(),
+    };
+    let mut i = 0;
+    for y in start_pos.y..=end_pos.y {
+        for z in start_pos.z..=end_pos.z {
+            for x in start_pos.x..=end_pos.x {
+                let pos = BlockPos::new(x, y, z);
+                let id = plot.get_block_raw(pos);
+                let block = plot.get_block(BlockPos::new(x, y, z));
+                if block.has_block_entity() {
+                    if let Some(block_entity) = plot.get_block_entity(pos) {
+                        cb.block_entities
+                            .insert(pos - start_pos, block_entity.clone());
+                    }
+                }
+                cb.data.set_entry(i, id);
+                i += 1;
+            }
+        }
+    }
+    cb
+}
+
+fn clear_area(plot: &mut Plot, first_pos: BlockPos, second_pos: BlockPos) {
+    let start_pos = first_pos.min(second_pos);
+    let end_pos = first_pos.max(second_pos);
+    for y in start_pos.y..=end_pos.y {
+        for z in start_pos.z..=end_pos.z {
+            for x in start_pos.x..=end_pos.x {
+                plot.set_block_raw(BlockPos::new(x, y, z), 0);
+            }
+        }
+    }
+    // Send modified chunks
+    let chunks = ((start_pos.x >> 4)..=(end_pos.x >> 4)).flat_map(|chunk_x| {
+        ((start_pos.z >> 4)..=(end_pos.z >> 4)).map(move |chunk_z| (chunk_x, chunk_z))
+    });
+    worldedit_send_chunks(plot, chunks, false);
+}
+
+fn paste_clipboard(plot: &mut Plot, cb: &WorldEditClipboard, pos: BlockPos, ignore_air: bool) {
+    write_clipboard(plot, cb, pos, ignore_air);
+    send_clipboard_area(plot, cb, pos);
+}
+
+/// Writes the blocks and block entities of the clipboard into the world without sending them.
+fn write_clipboard(plot: &mut Plot, cb: &WorldEditClipboard, pos: BlockPos, ignore_air: bool) {
+    let offset_x = pos.x - cb.offset_x;
+    let offset_y = pos.y - cb.offset_y;
+    let offset_z = pos.z - cb.offset_z;
+    let mut i = 0;
+    // This can be made better, but right now it's not D:
+    let x_range = offset_x..offset_x + cb.size_x as i32;
+    let y_range = offset_y..offset_y + cb.size_y as i32;
+    let z_range = offset_z..offset_z + cb.size_z as i32;
+
+    let entries = cb.data.entries();
+    // I have no clue if these clones are going to cost anything noticeable.
+    'top_loop: for y in y_range.clone() {
+        for z in z_range.clone() {
+            for x in x_range.clone() {
+                if i >= entries {
+                    break 'top_loop;
+                }
+                let entry = cb.data.get_entry(i);
+                i += 1;
+                if ignore_air && entry == 0 {
+                    continue;
+                }
+                plot.set_block_raw(BlockPos::new(x, y, z), entry);
+            }
+        }
+    }
+    // Block entities are stored before the chunks get sent so that they're part of the chunk data
+    for (pos, block_entity) in &cb.block_entities {
+        let pos = *pos + BlockPos::new(offset_x, offset_y, offset_z);
+        if let Some(chunk) = plot.get_chunk_mut(pos.x >> 4, pos.z >> 4) {
+            chunk.set_block_entity(
+                BlockPos::new(pos.x & 0xF, pos.y, pos.z & 0xF),
+                block_entity.clone(),
+            );
+        }
+    }
+}
+
+/// Resends the area that the clipboard was written to with `write_clipboard`.
+fn send_clipboard_area(plot: &mut Plot, cb: &WorldEditClipboard, pos: BlockPos) {
+    let offset_x = pos.x - cb.offset_x;
+    let offset_y = pos.y - cb.offset_y;
+    let offset_z = pos.z - cb.offset_z;
+    // Calculate the ranges of chunks that might have been modified
+    let chunk_x_range = offset_x >> 4..=(offset_x + cb.size_x as i32) >> 4;
+    let chunk_z_range = offset_z >> 4..=(offset_z + cb.size_z as i32) >> 4;
+    let chunks = chunk_x_range
+        .flat_map(|chunk_x| chunk_z_range.clone().map(move |chunk_z| (chunk_x, chunk_z)));
+    worldedit_send_chunks(plot, chunks, false);
+    // Send the block entity data as well so that signs show their text right away
+    for (pos, block_entity) in &cb.block_entities {
+        let pos = *pos + BlockPos::new(offset_x, offset_y, offset_z);
+        plot.send_block_entity_data(pos, block_entity);
+    }
+}
+
+fn capture_undo(plot: &mut Plot, player: usize, first_pos: BlockPos, second_pos: BlockPos) {
+    let origin = first_pos.min(second_pos);
+    let cb = create_clipboard(plot, origin, first_pos, second_pos);
+    let undo = WorldEditUndo {
+        changes: WorldEditUndoChanges::Snapshot {
+            clipboard: cb,
+            pos: origin,
+        },
+        plot_x: plot.x,
+        plot_z: plot.z,
+    };
+    plot.players[player].worldedit_undo.push(undo);
+}
+
+const COPY_ANCHORS: [&str; 6] = ["me", "center", "min", "max", "pos1", "pos2"];
+
+/// Finds the position a copied clipboard is anchored to. The clipboard gets pasted relative
+/// to this position, so anchoring it to the selection makes pasting independent of where the
+/// player was standing.
+fn copy_anchor(ctx: &mut CommandExecuteContext<'_>) -> Option<BlockPos> {
+    let anchor = ctx.arguments[0].unwrap_string().clone();
+    let player = ctx.get_player_mut();
+    let first_pos = player.first_position.unwrap();
+    let second_pos = player.second_position.unwrap();
+    let start_pos = first_pos.min(second_pos);
+    let end_pos = first_pos.max(second_pos);
+    match anchor.as_str() {
+        "me" => Some(BlockPos::new(
+            player.x.floor() as i32,
+            player.y.floor() as i32,
+            player.z.floor() as i32,
+        )),
+        "center" => Some(BlockPos::new(
+            (start_pos.x + end_pos.x).div_euclid(2),
+            (start_pos.y + end_pos.y).div_euclid(2),
+            (start_pos.z + end_pos.z).div_euclid(2),
+        )),
+        "min" => Some(start_pos),
+        "max" => Some(end_pos),
+        "pos1" => Some(first_pos),
+        "pos2" => Some(second_pos),
+        _ => {
+            player.send_error_message(&format!(
+                "Unknown anchor: {}. Use one of {}.",
+                anchor,
+                COPY_ANCHORS.join(", ")
+            ));
+            None
+        }
+    }
+}
+
+fn execute_copy(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let origin = match copy_anchor(&mut ctx) {
+        Some(origin) => origin,
+        None => return,
+    };
+    let mut clipboard = create_clipboard(
+        ctx.plot,
+        origin,
+        ctx.get_player().first_position.unwrap(),
+        ctx.get_player().second_position.unwrap(),
+    );
+    if ctx.has_flag('t') {
+        clipboard = match clipboard.trim() {
+            Some(clipboard) => clipboard,
+            None => {
+                ctx.get_player_mut()
+                    .send_error_message("Your selection only contains air.");
+                return;
+            }
+        };
+    }
+    ctx.get_player_mut().worldedit_clipboard = Some(Arc::new(clipboard));
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "Your selection was copied. ({:?})",
+        start_time.elapsed()
+    ));
+}
+
+fn execute_cut(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let first_pos = ctx.get_player().first_position.unwrap();
+    let second_pos = ctx.get_player().second_position.unwrap();
+
+    if ctx.has_flag('n') {
+        let operation = worldedit_start_operation(ctx.plot, ctx.player_idx);
+        let kind = WorldEditJobKind::Set {
+            pattern: WorldEditPattern::from_str("air").unwrap(),
+            filled: None,
+        };
+        start_dry_run(&mut ctx, operation, start_time, kind);
+        return;
+    }
+
+    let origin = match copy_anchor(&mut ctx) {
+        Some(origin) => origin,
+        None => return,
+    };
+    let clipboard = create_clipboard(ctx.plot, origin, first_pos, second_pos);
+    ctx.get_player_mut().worldedit_clipboard = Some(Arc::new(clipboard));
+    clear_area(ctx.plot, first_pos, second_pos);
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "Your selection was cut. ({:?})",
+        start_time.elapsed()
+    ));
+}
+
+fn execute_move(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let move_amt = ctx.arguments[0].unwrap_uint();
+    let direction = ctx.arguments[1].unwrap_direction();
+
+    let first_pos = ctx.get_player().first_position.unwrap();
+    let second_pos = ctx.get_player().second_position.unwrap();
+    let start_pos = first_pos.min(second_pos);
+    let end_pos = first_pos.max(second_pos);
+
+    let zero_pos = BlockPos::new(0, 0, 0);
+    let delta = direction.offset_pos(zero_pos, move_amt as i32);
+    let (new_start, new_end) = (start_pos + delta, end_pos + delta);
+    if !Plot::in_plot_bounds(ctx.plot.x, ctx.plot.z, new_start.x, new_start.z)
+        || !Plot::in_plot_bounds(ctx.plot.x, ctx.plot.z, new_end.x, new_end.z)
+        || new_start.y < 0
+        || new_end.y > 255
+    {
+        ctx.get_player_mut()
+            .send_error_message("The result would not fit inside of the plot.");
+        return;
+    }
+
+    // The whole move is done inside a clipboard covering both the source and the
+    // destination so that overlapping blocks are never overwritten before they are read.
+    let union_start = start_pos.min(new_start);
+    let union_end = end_pos.max(new_end);
+    let mut timings = Timings::new();
+    let mut clipboard = create_clipboard(ctx.plot, union_start, union_start, union_end);
+    timings.end_phase("copy");
+    let undo = WorldEditUndo {
+        changes: WorldEditUndoChanges::Snapshot {
+            clipboard: clipboard.clone(),
+            pos: union_start,
+        },
+        plot_x: ctx.plot.x,
+        plot_z: ctx.plot.z,
+    };
+    ctx.get_player_mut().worldedit_undo.push(undo);
+    timings.end_phase("undo snapshot");
+    clipboard.shift_region(
+        start_pos - union_start,
+        end_pos - union_start,
+        delta,
+        ctx.has_flag('a'),
+    );
+    timings.end_phase("shift");
+    write_clipboard(ctx.plot, &clipboard, union_start, false);
+    timings.end_phase("block writes");
+    send_clipboard_area(ctx.plot, &clipboard, union_start);
+    timings.end_phase("sending chunks");
+
+    if ctx.has_flag('s') {
+        let first_pos = first_pos + delta;
+        let second_pos = second_pos + delta;
+        let player = ctx.get_player_mut();
+        player.worldedit_set_first_position(first_pos.x, first_pos.y, first_pos.z);
+        player.worldedit_set_second_position(second_pos.x, second_pos.y, second_pos.z);
+    }
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "Your selection was moved. ({:?})",
+        start_time.elapsed()
+    ));
+    if ctx.has_flag('t') {
+        ctx.get_player_mut()
+            .send_worldedit_message(&timings.summary());
+    }
+}
+
+/// Replaces the contents of the selection with `transform` applied to them. The result is
+/// centered on the selection. Everything is done in one go so that it can be undone at once.
+fn transform_selection(
+    ctx: &mut CommandExecuteContext<'_>,
+    transform: impl Fn(&WorldEditClipboard) -> WorldEditClipboard,
+) -> bool {
+    let first_pos = ctx.get_player().first_position.unwrap();
+    let second_pos = ctx.get_player().second_position.unwrap();
+    let start_pos = first_pos.min(second_pos);
+    let end_pos = first_pos.max(second_pos);
+
+    if !check_queue_empty(ctx) {
+        return false;
+    }
+    let transformed = transform(&create_clipboard(ctx.plot, start_pos, start_pos, end_pos));
+    let size = end_pos - start_pos + BlockPos::new(1, 1, 1);
+    let new_start = BlockPos::new(
+        start_pos.x + (size.x - transformed.size_x as i32) / 2,
+        start_pos.y,
+        start_pos.z + (size.z - transformed.size_z as i32) / 2,
+    );
+    let new_end = new_start
+        + BlockPos::new(
+            transformed.size_x as i32 - 1,
+            transformed.size_y as i32 - 1,
+            transformed.size_z as i32 - 1,
+        );
+    if !Plot::in_plot_bounds(ctx.plot.x, ctx.plot.z, new_start.x, new_start.z)
+        || !Plot::in_plot_bounds(ctx.plot.x, ctx.plot.z, new_end.x, new_end.z)
+    {
+        ctx.get_player_mut()
+            .send_error_message("The result would not fit inside of the plot.");
+        return false;
+    }
+
+    // Like with //move, the source is cleared and the result written inside of one clipboard
+    // covering both of them
+    let union_start = start_pos.min(new_start);
+    let union_end = end_pos.max(new_end);
+    let mut clipboard = create_clipboard(ctx.plot, union_start, union_start, union_end);
+    let undo = WorldEditUndo {
+        changes: WorldEditUndoChanges::Snapshot {
+            clipboard: clipboard.clone(),
+            pos: union_start,
+        },
+        plot_x: ctx.plot.x,
+        plot_z: ctx.plot.z,
+    };
+    ctx.get_player_mut().worldedit_undo.push(undo);
+
+    for y in start_pos.y..=end_pos.y {
+        for z in start_pos.z..=end_pos.z {
+            for x in start_pos.x..=end_pos.x {
+                let pos = BlockPos::new(x, y, z);
+                ctx.plot.delete_block_entity(pos);
+                let pos = pos - union_start;
+                let idx = clipboard.index(pos.x, pos.y, pos.z);
+                clipboard.data.set_entry(idx, 0);
+                clipboard.block_entities.remove(&pos);
+            }
+        }
+    }
+    let delta = new_start - union_start;
+    for y in 0..transformed.size_y as i32 {
+        for z in 0..transformed.size_z as i32 {
+            for x in 0..transformed.size_x as i32 {
+                let id = transformed.data.get_entry(transformed.index(x, y, z));
+                let pos = BlockPos::new(x, y, z) + delta;
+                let idx = clipboard.index(pos.x, pos.y, pos.z);
+                clipboard.data.set_entry(idx, id);
+            }
+        }
+    }
+    for (pos, block_entity) in transformed.block_entities {
+        clipboard.block_entities.insert(pos + delta, block_entity);
+    }
+    paste_clipboard(ctx.plot, &clipboard, union_start, false);
+
+    let player = ctx.get_player_mut();
+    player.worldedit_set_first_position(new_start.x, new_start.y, new_start.z);
+    player.worldedit_set_second_position(new_end.x, new_end.y, new_end.z);
+    true
+}
+
+fn execute_rotate(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let degrees = ctx.arguments[0].unwrap_uint();
+    if !degrees.is_multiple_of(90) {
+        ctx.get_player_mut()
+            .send_error_message("You can only rotate by multiples of 90 degrees.");
+        return;
+    }
+    let turns = degrees / 90;
+
+    if ctx.has_flag('s') {
+        if transform_selection(&mut ctx, |cb| cb.rotate(turns)) {
+            ctx.get_player_mut().send_worldedit_message(&format!(
+                "Your selection was rotated. ({:?})",
+                start_time.elapsed()
+            ));
+        }
+        return;
+    }
+
+    let player = ctx.get_player_mut();
+    match player.worldedit_clipboard.take() {
+        Some(cb) => {
+            player.worldedit_clipboard = Some(Arc::new(cb.rotate(turns)));
+            player.send_worldedit_message(&format!(
+                "The clipboard copy has been rotated. ({:?})",
+                start_time.elapsed()
+            ));
+        }
+        None => player.send_error_message("Your clipboard is empty. Use //copy first."),
+    }
+}
+
+fn execute_flip(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let facing = *ctx.arguments[0].unwrap_direction();
+    let flip = |cb: &WorldEditClipboard| match facing {
+        BlockFacing::North => cb.flip(BlockDirection::North),
+        BlockFacing::South => cb.flip(BlockDirection::South),
+        BlockFacing::East => cb.flip(BlockDirection::East),
+        BlockFacing::West => cb.flip(BlockDirection::West),
+        BlockFacing::Up | BlockFacing::Down => cb.flip_vertical(),
+    };
+
+    if ctx.has_flag('s') {
+        if transform_selection(&mut ctx, flip) {
+            ctx.get_player_mut().send_worldedit_message(&format!(
+                "Your selection was flipped. ({:?})",
+                start_time.elapsed()
+            ));
+        }
+        return;
+    }
+
+    let player = ctx.get_player_mut();
+    match player.worldedit_clipboard.take() {
+        Some(cb) => {
+            player.worldedit_clipboard = Some(Arc::new(flip(&cb)));
+            player.send_worldedit_message(&format!(
+                "The clipboard copy has been flipped. ({:?})",
+                start_time.elapsed()
+            ));
+        }
+        None => player.send_error_message("Your clipboard is empty. Use //copy first."),
+    }
+}
+
+fn execute_clearclipboard(mut ctx: CommandExecuteContext<'_>) {
+    let player = ctx.get_player_mut();
+    player.worldedit_clipboard = None;
+    player.send_worldedit_message("Clipboard cleared.");
+}
+
+fn execute_paste(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let cb = match &ctx.get_player().worldedit_clipboard {
+        Some(cb) => Arc::clone(cb),
+        None => {
+            ctx.get_player_mut()
+                .send_system_message("Your clipboard is empty!");
+            return;
+        }
+    };
+    let pos = BlockPos::new(
+        ctx.get_player().x.floor() as i32,
+        ctx.get_player().y.floor() as i32,
+        ctx.get_player().z.floor() as i32,
+    );
+    let origin = pos - BlockPos::new(cb.offset_x, cb.offset_y, cb.offset_z);
+    let size = BlockPos::new(cb.size_x as i32, cb.size_y as i32, cb.size_z as i32);
+    let mut timings = Timings::new();
+    capture_undo(ctx.plot, ctx.player_idx, origin, origin + size);
+    timings.end_phase("undo snapshot");
+
+    // Large clipboards are pasted over several updates like any other big operation
+    let operation = WorldEditOperation::new(origin, origin + size - BlockPos::new(1, 1, 1));
+    let mut job = WorldEditJob::new(
+        ctx.get_player().uuid,
+        WorldEditJobKind::Paste {
+            clipboard: cb,
+            origin,
+            ignore_air: ctx.has_flag('a'),
+            mask: ctx
+                .get_flag_argument('m')
+                .map(|arg| arg.unwrap_mask().clone()),
+        },
+        operation,
+        start_time,
+    );
+    job.timings = timings;
+    job.show_timings = ctx.has_flag('t');
+    worldedit_run_job(ctx.plot, ctx.player_idx, job);
+}
+
+/// Returns false and tells the player if an operation affecting `blocks` blocks is over their
+/// limit.
+fn check_limit(ctx: &mut CommandExecuteContext<'_>, blocks: usize) -> bool {
+    let limit = ctx.get_player().worldedit_limit;
+    if blocks > limit {
+        ctx.get_player_mut().send_error_message(&format!(
+            "Operation would affect {} blocks, over your limit of {}. Use //limit to change it.",
+            blocks, limit
+        ));
+        return false;
+    }
+    true
+}
+
+/// Moves `pos` to the nearest position that is inside of the plot.
+fn clamp_to_plot(plot: &Plot, pos: BlockPos) -> BlockPos {
+    BlockPos::new(
+        pos.x.clamp(plot.x * 256, plot.x * 256 + 255),
+        pos.y.clamp(0, 255),
+        pos.z.clamp(plot.z * 256, plot.z * 256 + 255),
+    )
+}
+
+/// Sets every block between `first_pos` and `second_pos` that `in_shape` returns true for
+/// using `pattern`. The region is clamped to the plot. Returns the amount of blocks changed.
+fn generate_shape(
+    ctx: &mut CommandExecuteContext<'_>,
+    pattern: &WorldEditPattern,
+    first_pos: BlockPos,
+    second_pos: BlockPos,
+    in_shape: impl Fn(BlockPos) -> bool,
+) -> Option<usize> {
+    let first_pos = clamp_to_plot(ctx.plot, first_pos);
+    let second_pos = clamp_to_plot(ctx.plot, second_pos);
+    let mut operation = WorldEditOperation::new(first_pos, second_pos);
+    if !check_limit(ctx, operation.volume()) {
+        return None;
+    }
+    capture_undo(ctx.plot, ctx.player_idx, first_pos, second_pos);
+
+    for x in operation.x_range.clone() {
+        for y in operation.y_range.clone() {
+            for z in operation.z_range.clone() {
+                let block_pos = BlockPos::new(x, y, z);
+                if in_shape(block_pos)
+                    && ctx
+                        .plot
+                        .set_block_raw(block_pos, pattern.pick_at(block_pos).get_id())
+                {
+                    operation.update_block(block_pos);
+                }
+            }
+        }
+    }
+
+    let blocks_updated = operation.blocks_updated();
+    worldedit_send_operation(ctx.plot, operation);
+    Some(blocks_updated)
+}
+
+/// Parses the radii of a sphere in the form of `r` or `x,y,z`. Missing radii default to the
+/// first one.
+fn parse_radii(arg: &str) -> Option<[u32; 3]> {
+    let radii = arg
+        .split(',')
+        .map(|radius| radius.parse::<u32>().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    match radii.as_slice() {
+        [r] => Some([*r, *r, *r]),
+        [x, y] => Some([*x, *y, *x]),
+        [x, y, z] => Some([*x, *y, *z]),
+        _ => None,
+    }
+}
+
+fn execute_sphere(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let pattern = ctx.arguments[0].unwrap_pattern().clone();
+    let radii = match parse_radii(ctx.arguments[1].unwrap_string()) {
+        Some(radii) => radii,
+        None => {
+            ctx.get_player_mut()
+                .send_error_message("Radii must be one number or three comma separated numbers.");
+            return;
+        }
+    };
+    let origin = BlockPos::new(
+        ctx.get_player().x.floor() as i32,
+        ctx.get_player().y.floor() as i32,
+        ctx.get_player().z.floor() as i32,
+    );
+
+    let [rx, ry, rz] = radii;
+    let first_pos = BlockPos::new(
+        origin.x - rx as i32,
+        origin.y - ry as i32,
+        origin.z - rz as i32,
+    );
+    let second_pos = BlockPos::new(
+        origin.x + rx as i32,
+        origin.y + ry as i32,
+        origin.z + rz as i32,
+    );
+    let blocks_updated = generate_shape(&mut ctx, &pattern, first_pos, second_pos, |pos| {
+        // Each axis is normalized by its own radius, which turns the sphere into an ellipsoid
+        let nx = (pos.x - origin.x) as f64 / (rx as f64 + 0.5);
+        let ny = (pos.y - origin.y) as f64 / (ry as f64 + 0.5);
+        let nz = (pos.z - origin.z) as f64 / (rz as f64 + 0.5);
+        nx * nx + ny * ny + nz * nz <= 1.0
+    });
+    let blocks_updated = match blocks_updated {
+        Some(blocks_updated) => blocks_updated,
+        None => return,
+    };
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "{} block(s) have been created. ({:?})",
+        blocks_updated,
+        start_time.elapsed()
+    ));
+}
+
+/// Places the pattern wherever the expression is positive. The coordinates are normalized so
+/// that they go from -1 to 1 across the selection.
+fn execute_generate(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let pattern = ctx.arguments[0].unwrap_pattern().clone();
+    let expression = match Expression::parse(ctx.arguments[1].unwrap_string()) {
+        Ok(expression) => expression,
+        Err(err) => {
+            ctx.get_player_mut().send_error_message(&err.to_string());
+            return;
+        }
+    };
+
+    let first_pos = ctx.get_player().first_position.unwrap();
+    let second_pos = ctx.get_player().second_position.unwrap();
+    let start = first_pos.min(second_pos);
+    let end = first_pos.max(second_pos);
+    let normalize = |val: i32, start: i32, end: i32| {
+        if start == end {
+            0.0
+        } else {
+            (val - start) as f64 / (end - start) as f64 * 2.0 - 1.0
+        }
+    };
+    let blocks_updated = generate_shape(&mut ctx, &pattern, start, end, |pos| {
+        let x = normalize(pos.x, start.x, end.x);
+        let y = normalize(pos.y, start.y, end.y);
+        let z = normalize(pos.z, start.z, end.z);
+        expression.eval(x, y, z) > 0.0
+    });
+    let blocks_updated = match blocks_updated {
+        Some(blocks_updated) => blocks_updated,
+        None => return,
+    };
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "{} block(s) have been created. ({:?})",
+        blocks_updated,
+        start_time.elapsed()
+    ));
+}
+
+fn execute_deform(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let mut expressions = Vec::new();
+    for arg in &ctx.arguments {
+        match Expression::parse(arg.unwrap_string()) {
+            Ok(expression) => expressions.push(expression),
+            Err(err) => {
+                ctx.get_player_mut().send_error_message(&err.to_string());
+                return;
+            }
+        }
+    }
+
+    let operation = worldedit_start_operation(ctx.plot, ctx.player_idx);
+    if !check_limit(&mut ctx, operation.volume()) {
+        return;
+    }
+
+    // Like with //generate, the coordinates go from -1 to 1 across the selection
+    let first_pos = ctx.get_player().first_position.unwrap();
+    let second_pos = ctx.get_player().second_position.unwrap();
+    let size = first_pos.max(second_pos) - first_pos.min(second_pos);
+    let normalize = |val: i32, size: i32| {
+        if size == 0 {
+            0.0
+        } else {
+            val as f64 / size as f64 * 2.0 - 1.0
+        }
+    };
+    let denormalize = |val: f64, size: i32| ((val + 1.0) / 2.0 * size as f64).round() as i32;
+    let deformed = transform_selection(&mut ctx, |cb| {
+        cb.deform(|pos| {
+            let x = normalize(pos.x, size.x);
+            let y = normalize(pos.y, size.y);
+            let z = normalize(pos.z, size.z);
+            BlockPos::new(
+                denormalize(expressions[0].eval(x, y, z), size.x),
+                denormalize(expressions[1].eval(x, y, z), size.y),
+                denormalize(expressions[2].eval(x, y, z), size.z),
+            )
+        })
+    });
+    if deformed {
+        ctx.get_player_mut().send_worldedit_message(&format!(
+            "Your selection was deformed. ({:?})",
+            start_time.elapsed()
+        ));
+    }
+}
+
+fn pyramid(mut ctx: CommandExecuteContext<'_>, hollow: bool) {
+    let start_time = Instant::now();
+
+    let pattern = ctx.arguments[0].unwrap_pattern().clone();
+    let size = ctx.arguments[1].unwrap_uint() as i32;
+    let origin = BlockPos::new(
+        ctx.get_player().x.floor() as i32,
+        ctx.get_player().y.floor() as i32,
+        ctx.get_player().z.floor() as i32,
+    );
+
+    // Each layer is a square that is one block smaller on every side than the layer below
+    let first_pos = BlockPos::new(origin.x - size, origin.y, origin.z - size);
+    let second_pos = BlockPos::new(origin.x + size, origin.y + size, origin.z + size);
+    let blocks_updated = generate_shape(&mut ctx, &pattern, first_pos, second_pos, |pos| {
+        let layer_size = size - (pos.y - origin.y);
+        let dx = (pos.x - origin.x).abs();
+        let dz = (pos.z - origin.z).abs();
+        if hollow {
+            (dx == layer_size && dz <= layer_size) || (dz == layer_size && dx <= layer_size)
+        } else {
+            dx <= layer_size && dz <= layer_size
+        }
+    });
+    let blocks_updated = match blocks_updated {
+        Some(blocks_updated) => blocks_updated,
+        None => return,
+    };
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "{} block(s) have been created. ({:?})",
+        blocks_updated,
+        start_time.elapsed()
+    ));
+}
+
+fn execute_pyramid(ctx: CommandExecuteContext<'_>) {
+    pyramid(ctx, false);
+}
+
+fn execute_hpyramid(ctx: CommandExecuteContext<'_>) {
+    pyramid(ctx, true);
+}
+
+fn execute_load(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let file_name = ctx.arguments[0].unwrap_string();
+
+    let clipboard = WorldEditClipboard::load_from_schematic(
+        Path::new(&CONFIG.schems_dir),
+        file_name,
+        ctx.get_player().uuid,
+    );
+    match clipboard {
+        Ok(cb) => {
+            ctx.get_player_mut().worldedit_clipboard = Some(Arc::new(cb));
+            ctx.get_player_mut().send_worldedit_message(&format!(
+                "The schematic was loaded to your clipboard. Do //paste to birth it into the world. ({:?})",
+                start_time.elapsed()
+            ));
+        }
+        Err(err) => {
+            ctx.get_player_mut().send_error_message(&format!(
+                "There was an error loading the schematic: {}",
+                err
+            ));
+        }
+    }
+}
+
+fn execute_download(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let player = ctx.get_player_mut();
+    let schematic = player
+        .worldedit_clipboard
+        .as_ref()
+        .unwrap()
+        .to_schematic_bytes();
+    let messages = clipboard_messages(&schematic);
+    let parts = messages.len();
+    for data in messages {
+        let plugin_message = C17PluginMessage {
+            channel: CLIPBOARD_CHANNEL.to_owned(),
+            data,
+        }
+        .encode();
+        player.client.send_packet(&plugin_message);
+    }
+    player.send_worldedit_message(&format!(
+        "Your clipboard was sent as a {} byte schematic in {} part(s). ({:?})",
+        schematic.len(),
+        parts,
+        start_time.elapsed()
+    ));
+}
+
+/// Returns where each copy of a region starting at `start_pos` with `size` goes when it is
+/// stacked `amount` times in `direction`. Each copy is placed right next to the previous one.
+fn stack_positions(
+    start_pos: BlockPos,
+    size: BlockPos,
+    direction: BlockFacing,
+    amount: u32,
+) -> Vec<BlockPos> {
+    let stack_offset = match direction {
+        BlockFacing::North | BlockFacing::South => size.z,
+        BlockFacing::East | BlockFacing::West => size.x,
+        BlockFacing::Up | BlockFacing::Down => size.y,
+    };
+    (1..=amount as i32)
+        .map(|i| direction.offset_pos(start_pos, i * stack_offset))
+        .collect()
+}
+
+fn execute_stack(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let stack_amt = ctx.arguments[0].unwrap_uint();
+    let direction = *ctx.arguments[1].unwrap_direction();
+    let first_pos = ctx.get_player().first_position.unwrap();
+    let second_pos = ctx.get_player().second_position.unwrap();
+    // The clipboard is anchored to the minimum corner so the copies line up no matter which
+    // corner the first position is
+    let start_pos = first_pos.min(second_pos);
+    let clipboard = create_clipboard(ctx.plot, start_pos, first_pos, second_pos);
+    let size = BlockPos::new(
+        clipboard.size_x as i32,
+        clipboard.size_y as i32,
+        clipboard.size_z as i32,
+    );
+    for block_pos in stack_positions(start_pos, size, direction, stack_amt) {
+        paste_clipboard(ctx.plot, &clipboard, block_pos, ctx.has_flag('a'));
+    }
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "Your clipboard was stacked. ({:?})",
+        start_time.elapsed()
+    ));
+}
+
+fn execute_undo(mut ctx: CommandExecuteContext<'_>) {
+    if ctx.get_player().worldedit_undo.is_empty() {
+        ctx.get_player_mut()
+            .send_error_message("There is nothing left to undo.");
+        return;
+    }
+
+    let count = ctx.arguments[0].unwrap_uint() as usize;
+    let mut undone = 0;
+    while undone < count {
+        let (plot_x, plot_z) = match ctx.get_player().worldedit_undo.last() {
+            Some(undo) => (undo.plot_x, undo.plot_z),
+            None => break,
+        };
+        if plot_x != ctx.plot.x || plot_z != ctx.plot.z {
+            ctx.get_player_mut()
+                .send_error_message("Cannot undo outside of your current plot.");
+            break;
+        }
+        let undo = ctx.get_player_mut().worldedit_undo.pop().unwrap();
+        match undo.changes {
+            WorldEditUndoChanges::Snapshot { clipboard, pos } => {
+                paste_clipboard(ctx.plot, &clipboard, pos, false)
+            }
+            WorldEditUndoChanges::Blocks(changes) => undo_block_changes(ctx.plot, changes),
+        }
+        undone += 1;
+    }
+    if undone > 0 {
+        ctx.get_player_mut()
+            .send_worldedit_message(&format!("Undid {} operation(s).", undone));
+    }
+}
+
+/// Restores the blocks changed by an operation. The changes are applied in reverse so that
+/// a block changed more than once ends up in its oldest state.
+fn undo_block_changes(plot: &mut Plot, changes: Vec<UndoBlockChange>) {
+    let mut chunks: Vec<(i32, i32)> = Vec::new();
+    for change in changes.into_iter().rev() {
+        plot.set_block_raw(change.pos, change.block_id);
+        plot.delete_block_entity(change.pos);
+        if let Some(block_entity) = change.block_entity {
+            plot.set_block_entity(change.pos, block_entity);
+        }
+        let chunk = (change.pos.x >> 4, change.pos.z >> 4);
+        if !chunks.contains(&chunk) {
+            chunks.push(chunk);
+        }
+    }
+    worldedit_send_chunks(plot, chunks, false);
+}
+
+fn execute_limit(mut ctx: CommandExecuteContext<'_>) {
+    let max_limit = CONFIG.worldedit_max_limit as usize;
+    let limit = match ctx.arguments[0].unwrap_string().as_str() {
+        "default" => CONFIG.worldedit_limit as usize,
+        limit => match limit.parse::<usize>() {
+            Ok(limit) if limit <= max_limit => limit,
+            Ok(_) => {
+                ctx.get_player_mut()
+                    .send_error_message(&format!("Your limit can't be higher than {}.", max_limit));
+                return;
+            }
+            Err(_) => {
+                ctx.get_player_mut()
+                    .send_error_message("The limit must be a number or default.");
+                return;
+            }
+        },
+    };
+    let player = ctx.get_player_mut();
+    player.worldedit_limit = limit;
+    player.send_worldedit_message(&format!("Block change limit set to {}.", limit));
+}
+
+const HELP_PAGE_SIZE: usize = 8;
+
+fn execute_help(mut ctx: CommandExecuteContext<'_>) {
+    let query = ctx.arguments[0].unwrap_string().clone();
+    let query = query.trim_start_matches('/');
+    let player = ctx.get_player_mut();
+
+    if let Ok(page) = query.parse::<usize>() {
+        let mut names: Vec<&str> = COMMANDS.keys().copied().collect();
+        names.sort_unstable();
+        let pages = names.len().div_ceil(HELP_PAGE_SIZE);
+        if page == 0 || page > pages {
+            player.send_error_message(&format!("Page must be between 1 and {}.", pages));
+            return;
+        }
+        player.send_worldedit_message(&format!(
+            "--- Worldedit commands (page {}/{}) ---",
+            page, pages
+        ));
+        for name in names
+            .iter()
+            .skip((page - 1) * HELP_PAGE_SIZE)
+            .take(HELP_PAGE_SIZE)
+        {
+            player.send_system_message(&format!("//{} - {}", name, COMMANDS[name].description));
+        }
+        if page < pages {
+            player.send_worldedit_message(&format!("Use //help {} for the next page.", page + 1));
+        }
+        return;
+    }
+
+    let command = match find_command(query) {
+        Some(command) => command,
+        None => {
+            player.send_error_message(&format!("Unknown command: //{}", query));
+            return;
+        }
+    };
+    let usage: Vec<String> = command
+        .arguments
+        .iter()
+        .map(|arg| {
+            let optional = arg.default.is_some()
+                || matches!(
+                    arg.argument_type,
+                    ArgumentType::UnsignedInteger
+                        | ArgumentType::Direction
+                        | ArgumentType::Position
+                );
+            if optional {
+                format!("[{}]", arg.name)
+            } else {
+                format!("<{}>", arg.name)
+            }
+        })
+        .collect();
+    player.send_worldedit_message(&format!("//{} {}", query, usage.join(" ")));
+    player.send_system_message(command.description);
+    for arg in command.arguments {
+        player.send_system_message(&format!(
+            "  {} ({:?}) - {}",
+            arg.name, arg.argument_type, arg.description
+        ));
+    }
+    for flag in command.flags {
+        player.send_system_message(&format!("  -{} - {}", flag.letter, flag.description));
+    }
+}
+
+fn execute_sel(mut ctx: CommandExecuteContext<'_>) {
+    let selector = ctx.arguments[0].unwrap_string().clone();
+    let mode = match selector.as_str() {
+        "" => None,
+        "cuboid" => Some(SelectionMode::Cuboid),
+        "extend" => Some(SelectionMode::Extend),
+        _ => {
+            ctx.get_player_mut().send_error_message(&format!(
+                "Unknown selector: {}. The available selectors are cuboid and extend.",
+                selector
+            ));
+            return;
+        }
+    };
+    let player = ctx.get_player_mut();
+    player.first_position = None;
+    player.second_position = None;
+    match mode {
+        Some(mode) => {
+            player.worldedit_selection_mode = mode;
+            player.send_worldedit_message(&format!("Selector set to {}.", selector));
+        }
+        None => player.send_worldedit_message("Selection cleared."),
+    }
+    player.worldedit_send_selection_cui();
+}
+
+/// How many snapshots a plot can have at once, since they're all kept in memory
+const MAX_SNAPSHOTS: usize = 16;
+
+fn execute_snapshot(mut ctx: CommandExecuteContext<'_>) {
+    let action = ctx.arguments[0].unwrap_string().clone();
+    let name = ctx.arguments[1].unwrap_string().clone();
+    if action == "list" {
+        let mut names: Vec<&String> = ctx.plot.worldedit_snapshots.keys().collect();
+        names.sort();
+        let message = if names.is_empty() {
+            "There are no snapshots on this plot.".to_owned()
+        } else {
+            format!(
+                "Snapshots: {}",
+                names
+                    .iter()
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+        ctx.get_player_mut().send_worldedit_message(&message);
+        return;
+    }
+    if action != "save" && action != "restore" {
+        ctx.get_player_mut().send_error_message(&format!(
+            "Unknown action: {}. Use save, restore or list.",
+            action
+        ));
+        return;
+    }
+    if name.is_empty() {
+        ctx.get_player_mut()
+            .send_error_message("The snapshot needs a name.");
+        return;
+    }
+    // Snapshots of a partly done job couldn't be restored to anything that existed
+    if !check_queue_empty(&mut ctx) {
+        return;
+    }
+
+    if action == "save" {
+        if !check_selection(&mut ctx) {
+            return;
+        }
+        let operation = worldedit_start_operation(ctx.plot, ctx.player_idx);
+        if !check_limit(&mut ctx, operation.volume()) {
+            return;
+        }
+        if ctx.plot.worldedit_snapshots.len() >= MAX_SNAPSHOTS
+            && !ctx.plot.worldedit_snapshots.contains_key(&name)
+        {
+            ctx.get_player_mut().send_error_message(&format!(
+                "A plot can't have more than {} snapshots.",
+                MAX_SNAPSHOTS
+            ));
+            return;
+        }
+        let first_pos = ctx.get_player().first_position.unwrap();
+        let second_pos = ctx.get_player().second_position.unwrap();
+        // The origin is the world origin so that the offsets remember where the region was
+        let clipboard = create_clipboard(ctx.plot, BlockPos::new(0, 0, 0), first_pos, second_pos);
+        ctx.plot.worldedit_snapshots.insert(name.clone(), clipboard);
+        ctx.get_player_mut()
+            .send_worldedit_message(&format!("Saved the selection as snapshot {}.", name));
+        return;
+    }
+
+    let clipboard = match ctx.plot.worldedit_snapshots.get(&name) {
+        Some(clipboard) => clipboard.clone(),
+        None => {
+            ctx.get_player_mut()
+                .send_error_message(&format!("There is no snapshot called {}.", name));
+            return;
+        }
+    };
+    let start_pos = BlockPos::new(
+        -clipboard.offset_x,
+        -clipboard.offset_y,
+        -clipboard.offset_z,
+    );
+    let end_pos = start_pos
+        + BlockPos::new(
+            clipboard.size_x as i32 - 1,
+            clipboard.size_y as i32 - 1,
+            clipboard.size_z as i32 - 1,
+        );
+    capture_undo(ctx.plot, ctx.player_idx, start_pos, end_pos);
+    paste_clipboard(ctx.plot, &clipboard, BlockPos::new(0, 0, 0), false);
+    ctx.get_player_mut()
+        .send_worldedit_message(&format!("Restored snapshot {}.", name));
+}
+
+/// Resolves a direction relative to the player. Forward and back use the player's `facing`,
+/// which can be up or down, while left and right are relative to the `horizontal` direction
+/// the player is looking in.
+fn relative_direction(
+    name: &str,
+    facing: BlockFacing,
+    horizontal: BlockDirection,
+) -> Option<BlockFacing> {
+    match name {
+        "me" | "forward" | "f" => Some(facing),
+        "back" | "b" => Some(facing.opposite()),
+        "left" | "l" => Some(horizontal.rotate_ccw().block_facing()),
+        "right" | "r" => Some(horizontal.rotate().block_facing()),
+        _ => None,
+    }
+}
+
+/// Parses coordinates in the form of `x,y,z`. Each coordinate can be prefixed with `~` to make it
+/// relative to `relative_to`, just like in vanilla commands.
+fn parse_position(arg: &str, relative_to: BlockPos) -> Option<BlockPos> {
+    let parse_coord = |coord: &str, base: i32| -> Option<i32> {
+        if let Some(offset) = coord.strip_prefix('~') {
+            if offset.is_empty() {
+                Some(base)
+            } else {
+                offset.parse::<i32>().ok().map(|offset| base + offset)
+            }
+        } else {
+            coord.parse::<i32>().ok()
+        }
+    };
+    let coords: Vec<&str> = arg.split(',').collect();
+    if coords.len() != 3 {
+        return None;
+    }
+    Some(BlockPos::new(
+        parse_coord(coords[0], relative_to.x)?,
+        parse_coord(coords[1], relative_to.y)?,
+        parse_coord(coords[2], relative_to.z)?,
+    ))
+}
+
+fn check_position_in_plot(ctx: &mut CommandExecuteContext<'_>, pos: BlockPos) -> bool {
+    if !Plot::in_plot_bounds(ctx.plot.x, ctx.plot.z, pos.x, pos.z) || pos.y < 0 || pos.y > 255 {
+        ctx.get_player_mut()
+            .send_error_message("That position is outside of the plot!");
+        return false;
+    }
+    true
+}
+
+fn execute_pos1(mut ctx: CommandExecuteContext<'_>) {
+    let pos = ctx.arguments[0].unwrap_position();
+    if check_position_in_plot(&mut ctx, pos) {
+        ctx.get_player_mut()
+            .worldedit_set_first_position(pos.x, pos.y, pos.z);
+    }
+}
+
+fn execute_pos2(mut ctx: CommandExecuteContext<'_>) {
+    let pos = ctx.arguments[0].unwrap_position();
+    if check_position_in_plot(&mut ctx, pos) {
+        ctx.get_player_mut()
+            .worldedit_set_second_position(pos.x, pos.y, pos.z);
+    }
+}
+
+fn execute_setblock(mut ctx: CommandExecuteContext<'_>) {
+    let pos = ctx.arguments[0].unwrap_position();
+    if !check_position_in_plot(&mut ctx, pos) {
+        return;
+    }
+    let block_id = ctx.arguments[1].unwrap_pattern().pick_at(pos).get_id();
+
+    let undo = WorldEditUndo {
+        changes: WorldEditUndoChanges::Blocks(vec![UndoBlockChange {
+            pos,
+            block_id: ctx.plot.get_block_raw(pos),
+            block_entity: ctx.plot.get_block_entity(pos).cloned(),
+        }]),
+        plot_x: ctx.plot.x,
+        plot_z: ctx.plot.z,
+    };
+    if set_block_clearing_entity(ctx.plot, pos, block_id) {
+        ctx.get_player_mut().worldedit_undo.push(undo);
+        ctx.plot.send_block_change(pos, block_id);
+    }
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "The block at {}, {}, {} was set.",
+        pos.x, pos.y, pos.z
+    ));
+}
+
+/// Finds the first non-air block the player is looking at, up to `max_distance` blocks away.
+fn trace_target_block(plot: &Plot, player: &Player, max_distance: f64) -> Option<BlockPos> {
+    // The player's eyes are 1.62 blocks above their feet
+    let origin = [player.x, player.y + 1.62, player.z];
+    let yaw = (player.yaw as f64).to_radians();
+    let pitch = (player.pitch as f64).to_radians();
+    let dir = [
+        -yaw.sin() * pitch.cos(),
+        -pitch.sin(),
+        yaw.cos() * pitch.cos(),
+    ];
+
+    // Walk through every block the ray passes through, one block boundary at a time
+    let mut pos = [
+        origin[0].floor() as i32,
+        origin[1].floor() as i32,
+        origin[2].floor() as i32,
+    ];
+    let mut step = [0; 3];
+    let mut t_max = [f64::INFINITY; 3];
+    let mut t_delta = [f64::INFINITY; 3];
+    for axis in 0..3 {
+        if dir[axis] > 0.0 {
+            step[axis] = 1;
+            t_max[axis] = (pos[axis] as f64 + 1.0 - origin[axis]) / dir[axis];
+            t_delta[axis] = 1.0 / dir[axis];
+        } else if dir[axis] < 0.0 {
+            step[axis] = -1;
+            t_max[axis] = (origin[axis] - pos[axis] as f64) / -dir[axis];
+            t_delta[axis] = 1.0 / -dir[axis];
+        }
+    }
+
+    loop {
+        let block_pos = BlockPos::new(pos[0], pos[1], pos[2]);
+        if !Plot::in_plot_bounds(plot.x, plot.z, block_pos.x, block_pos.z)
+            || block_pos.y < 0
+            || block_pos.y > 255
+        {
+            return None;
+        }
+        if !matches!(plot.get_block(block_pos), Block::Air {}) {
+            return Some(block_pos);
+        }
+
+        let axis = if t_max[0] < t_max[1] && t_max[0] < t_max[2] {
+            0
+        } else if t_max[1] < t_max[2] {
+            1
+        } else {
+            2
+        };
+        if t_max[axis] > max_distance {
+            return None;
+        }
+        pos[axis] += step[axis];
+        t_max[axis] += t_delta[axis];
+    }
+}
+
+fn execute_hpos1(mut ctx: CommandExecuteContext<'_>) {
+    let max_distance = CONFIG.worldedit_trace_distance as f64;
+    match trace_target_block(ctx.plot, ctx.get_player(), max_distance) {
+        Some(pos) => ctx
+            .get_player_mut()
+            .worldedit_set_first_position(pos.x, pos.y, pos.z),
+        None => ctx
+            .get_player_mut()
+            .send_error_message("No block in sight!"),
+    }
+}
+
+fn execute_hpos2(mut ctx: CommandExecuteContext<'_>) {
+    let max_distance = CONFIG.worldedit_trace_distance as f64;
+    match trace_target_block(ctx.plot, ctx.get_player(), max_distance) {
+        Some(pos) => ctx
+            .get_player_mut()
+            .worldedit_set_second_position(pos.x, pos.y, pos.z),
+        None => ctx
+            .get_player_mut()
+            .send_error_message("No block in sight!"),
+    }
+}
+
+fn execute_info(mut ctx: CommandExecuteContext<'_>) {
+    let max_distance = CONFIG.worldedit_trace_distance as f64;
+    let pos = match trace_target_block(ctx.plot, ctx.get_player(), max_distance) {
+        Some(pos) => pos,
+        None => {
+            ctx.get_player_mut()
+                .send_error_message("No block in sight!");
+            return;
+        }
+    };
+    let block = ctx.plot.get_block(pos);
+    let mut name = match block.get_name() {
+        Some(name) => name.to_owned(),
+        None => format!("{:?}", block),
+    };
+    let properties: Vec<String> = block
+        .properties()
+        .iter()
+        .map(|(key, val)| format!("{}={}", key, val))
+        .collect();
+    if !properties.is_empty() {
+        name += &format!("[{}]", properties.join(","));
+    }
+    let has_block_entity = ctx.plot.get_block_entity(pos).is_some();
+
+    let player = ctx.get_player_mut();
+    player.send_worldedit_message(&format!(
+        "Block at ({}, {}, {}): {} (id {})",
+        pos.x,
+        pos.y,
+        pos.z,
+        name,
+        block.get_id()
+    ));
+    if has_block_entity {
+        player.send_worldedit_message("The block has a block entity.");
+    }
+}
+
+/// Runs a gaussian blur over a `size_x` by `size_z` height map `iterations` times. Heights past
+/// the edges of the map are taken from the nearest edge.
+fn smooth_height_map(heights: &[i32], size_x: usize, size_z: usize, iterations: u32) -> Vec<i32> {
+    // 5x5 kernel with a sigma of 1
+    const KERNEL: [f64; 5] = [0.054, 0.242, 0.399, 0.242, 0.054];
+
+    let mut current: Vec<f64> = heights.iter().map(|h| *h as f64).collect();
+    for _ in 0..iterations {
+        let mut next = vec![0.0; current.len()];
+        for x in 0..size_x {
+            for z in 0..size_z {
+                let mut total = 0.0;
+                let mut weights = 0.0;
+                for (i, kx) in KERNEL.iter().enumerate() {
+                    for (j, kz) in KERNEL.iter().enumerate() {
+                        let sx = (x as isize + i as isize - 2)
+                            .max(0)
+                            .min(size_x as isize - 1);
+                        let sz = (z as isize + j as isize - 2)
+                            .max(0)
+                            .min(size_z as isize - 1);
+                        total += current[sx as usize * size_z + sz as usize] * kx * kz;
+                        weights += kx * kz;
+                    }
+                }
+                next[x * size_z + z] = total / weights;
+            }
+        }
+        current = next;
+    }
+    current.iter().map(|h| h.round() as i32).collect()
+}
+
+fn execute_smooth(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+    let iterations = ctx.arguments[0].unwrap_uint();
+
+    let first_pos = ctx.get_player().first_position.unwrap();
+    let second_pos = ctx.get_player().second_position.unwrap();
+    let mut operation = worldedit_start_operation(ctx.plot, ctx.player_idx);
+    if !check_limit(&mut ctx, operation.volume()) {
+        return;
+    }
+    capture_undo(ctx.plot, ctx.player_idx, first_pos, second_pos);
+
+    let x_range = operation.x_range.clone();
+    let y_range = operation.y_range.clone();
+    let z_range = operation.z_range.clone();
+    let size_x = WorldEditOperation::range_len(&x_range);
+    let size_z = WorldEditOperation::range_len(&z_range);
+
+    // The surface of a column is its highest full block. Columns without one sit just below
+    // the selection.
+    let mut heights = Vec::with_capacity(size_x * size_z);
+    for x in x_range.clone() {
+        for z in z_range.clone() {
+            let height = y_range
+                .clone()
+                .rev()
+                .find(|y| ctx.plot.get_block(BlockPos::new(x, *y, z)).is_cube())
+                .unwrap_or(y_range.start() - 1);
+            heights.push(height);
+        }
+    }
+
+    let new_heights = smooth_height_map(&heights, size_x, size_z, iterations);
+    for (idx, (old_height, new_height)) in heights.into_iter().zip(new_heights).enumerate() {
+        let x = x_range.start() + (idx / size_z) as i32;
+        let z = z_range.start() + (idx % size_z) as i32;
+        let new_height = new_height.max(y_range.start() - 1).min(*y_range.end());
+        if old_height < *y_range.start() || old_height == new_height {
+            continue;
+        }
+
+        let surface = ctx.plot.get_block(BlockPos::new(x, old_height, z)).get_id();
+        let mut set_block = |plot: &mut Plot, y: i32, block_id: u32| {
+            let block_pos = BlockPos::new(x, y, z);
+            plot.delete_block_entity(block_pos);
+            if plot.set_block_raw(block_pos, block_id) {
+                operation.update_block(block_pos);
+            }
+        };
+        if new_height > old_height {
+            for y in old_height + 1..=new_height {
+                set_block(ctx.plot, y, surface);
+            }
+        } else {
+            for y in new_height + 1..=old_height {
+                set_block(ctx.plot, y, 0);
+            }
+            if new_height >= *y_range.start() {
+                set_block(ctx.plot, new_height, surface);
+            }
+        }
+    }
+
+    let blocks_updated = operation.blocks_updated();
+    worldedit_send_operation(ctx.plot, operation);
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "Terrain's height map smoothed. {} block(s) have been changed. ({:?})",
+        blocks_updated,
+        start_time.elapsed()
+    ));
+}
+
+fn execute_naturalize(mut ctx: CommandExecuteContext<'_>) {
+    const STONE: u32 = 1;
+
+    let start_time = Instant::now();
+
+    let first_pos = ctx.get_player().first_position.unwrap();
+    let second_pos = ctx.get_player().second_position.unwrap();
+    let mut operation = worldedit_start_operation(ctx.plot, ctx.player_idx);
+    if !check_limit(&mut ctx, operation.volume()) {
+        return;
+    }
+    capture_undo(ctx.plot, ctx.player_idx, first_pos, second_pos);
+
+    for x in operation.x_range.clone() {
+        for z in operation.z_range.clone() {
+            // How deep into the current run of solid blocks we are. Anything that isn't a full
+            // block starts a new run, so the tops of overhangs get grass too.
+            let mut depth = 0;
+            for y in operation.y_range.clone().rev() {
+                let block_pos = BlockPos::new(x, y, z);
+                if !ctx.plot.get_block(block_pos).is_cube() {
+                    depth = 0;
+                    continue;
+                }
+                let block_id = match depth {
+                    0 => GRASS_BLOCK,
+                    1..=3 => DIRT,
+                    _ => STONE,
+                };
+                depth += 1;
+                ctx.plot.delete_block_entity(block_pos);
+                if ctx.plot.set_block_raw(block_pos, block_id) {
+                    operation.update_block(block_pos);
+                }
+            }
+        }
+    }
+
+    let blocks_updated = operation.blocks_updated();
+    worldedit_send_operation(ctx.plot, operation);
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "{} block(s) have been made to look more natural. ({:?})",
+        blocks_updated,
+        start_time.elapsed()
+    ));
+}
+
+/// Resets the selection to the state new plots are generated in. The world is flat, so this
+/// means the ground layers of sandstone, with stone bricks on the plot borders, and air above.
+fn execute_regen(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+
+    let first_pos = ctx.get_player().first_position.unwrap();
+    let second_pos = ctx.get_player().second_position.unwrap();
+    let mut operation = worldedit_start_operation(ctx.plot, ctx.player_idx);
+    if !check_limit(&mut ctx, operation.volume()) {
+        return;
+    }
+    capture_undo(ctx.plot, ctx.player_idx, first_pos, second_pos);
+
+    for x in operation.x_range.clone() {
+        for y in operation.y_range.clone() {
+            for z in operation.z_range.clone() {
+                let block_pos = BlockPos::new(x, y, z);
+                let block_id = Chunk::generated_block(GENERATED_LAYERS, x, y, z);
+                // Generated terrain never has block entities
+                ctx.plot.delete_block_entity(block_pos);
+                if ctx.plot.set_block_raw(block_pos, block_id) {
+                    operation.update_block(block_pos);
+                }
+            }
+        }
+    }
+
+    let blocks_updated = operation.blocks_updated();
+    worldedit_send_operation(ctx.plot, operation);
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "Region regenerated: {} block(s) have been changed. ({:?})",
+        blocks_updated,
+        start_time.elapsed()
+    ));
+}
+
+const WATER: u32 = 34;
+const GRASS_BLOCK_SNOWY: u32 = 8;
+const GRASS_BLOCK: u32 = 9;
+const DIRT: u32 = 10;
+const SNOW_LAYERS: RangeInclusive<u32> = 3921..=3928;
+const ICE: u32 = 3929;
+
+/// Calls `change` with the highest non-air block of every column within `radius` of the
+/// player and sets the blocks it returns. Returns the amount of blocks changed, or `None` if
+/// the area is over the player's limit.
+fn change_surface(
+    ctx: &mut CommandExecuteContext<'_>,
+    radius: u32,
+    change: impl Fn(&Plot, BlockPos) -> Vec<(BlockPos, u32)>,
+) -> Option<usize> {
+    let radius = radius as i32;
+    let player = ctx.get_player();
+    let origin = BlockPos::new(player.x.floor() as i32, 0, player.z.floor() as i32);
+    let first_pos = clamp_to_plot(ctx.plot, origin - BlockPos::new(radius, 0, radius));
+    let second_pos = clamp_to_plot(ctx.plot, origin + BlockPos::new(radius, 255, radius));
+
+    let mut operation = WorldEditOperation::new(first_pos, second_pos);
+    let columns = WorldEditOperation::range_len(&operation.x_range)
+        * WorldEditOperation::range_len(&operation.z_range);
+    // At most two blocks change in each column
+    if !check_limit(ctx, columns * 2) {
+        return None;
+    }
+    capture_undo(ctx.plot, ctx.player_idx, first_pos, second_pos);
+
+    for x in operation.x_range.clone() {
+        for z in operation.z_range.clone() {
+            let dx = (x - origin.x) as i64;
+            let dz = (z - origin.z) as i64;
+            if dx * dx + dz * dz > radius as i64 * radius as i64 {
+                continue;
+            }
+            let surface = (0..=255)
+                .rev()
+                .map(|y| BlockPos::new(x, y, z))
+                .find(|&pos| ctx.plot.get_block_raw(pos) != 0);
+            let surface = match surface {
+                Some(surface) => surface,
+                None => continue,
+            };
+            for (pos, block_id) in change(ctx.plot, surface) {
+                if ctx.plot.set_block_raw(pos, block_id) {
+                    operation.update_block(pos);
+                }
+            }
+        }
+    }
+
+    let blocks_updated = operation.blocks_updated();
+    worldedit_send_operation(ctx.plot, operation);
+    Some(blocks_updated)
+}
+
+fn execute_snow(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+    let radius = ctx.arguments[0].unwrap_uint();
+
+    let blocks_updated = change_surface(&mut ctx, radius, |plot, surface| {
+        let surface_id = plot.get_block_raw(surface);
+        if surface_id == WATER {
+            return vec![(surface, ICE)];
+        }
+        let above = surface.offset(BlockFace::Top);
+        if above.y > 255 || SNOW_LAYERS.contains(&surface_id) || !plot.get_block(surface).is_cube()
+        {
+            return Vec::new();
+        }
+        let mut changes = vec![(above, *SNOW_LAYERS.start())];
+        if surface_id == GRASS_BLOCK {
+            changes.push((surface, GRASS_BLOCK_SNOWY));
+        }
+        changes
+    });
+    let blocks_updated = match blocks_updated {
+        Some(blocks_updated) => blocks_updated,
+        None => return,
+    };
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "{} surface(s) covered. Let it snow~ ({:?})",
+        blocks_updated,
+        start_time.elapsed()
+    ));
+}
+
+fn execute_thaw(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+    let radius = ctx.arguments[0].unwrap_uint();
+
+    let blocks_updated = change_surface(&mut ctx, radius, |plot, surface| {
+        let surface_id = plot.get_block_raw(surface);
+        if surface_id == ICE {
+            return vec![(surface, WATER)];
+        }
+        if !SNOW_LAYERS.contains(&surface_id) {
+            return Vec::new();
+        }
+        let mut changes = vec![(surface, 0)];
+        let below = surface.offset(BlockFace::Bottom);
+        if below.y >= 0 && plot.get_block_raw(below) == GRASS_BLOCK_SNOWY {
+            changes.push((below, GRASS_BLOCK));
+        }
+        changes
+    });
+    let blocks_updated = match blocks_updated {
+        Some(blocks_updated) => blocks_updated,
+        None => return,
+    };
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "{} block(s) have been thawed. ({:?})",
+        blocks_updated,
+        start_time.elapsed()
+    ));
+}
+
+fn execute_green(mut ctx: CommandExecuteContext<'_>) {
+    let start_time = Instant::now();
+    let radius = ctx.arguments[0].unwrap_uint();
+
+    let blocks_updated = change_surface(&mut ctx, radius, |plot, surface| {
+        if plot.get_block_raw(surface) == DIRT {
+            vec![(surface, GRASS_BLOCK)]
+        } else {
+            Vec::new()
+        }
+    });
+    let blocks_updated = match blocks_updated {
+        Some(blocks_updated) => blocks_updated,
+        None => return,
+    };
+
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "{} block(s) have been greened. ({:?})",
+        blocks_updated,
+        start_time.elapsed()
+    ));
+}
+
+fn execute_chunk(mut ctx: CommandExecuteContext<'_>) {
+    let player = ctx.get_player_mut();
+
+    let chunk_x = player.x.floor() as i32 >> 4;
+    let chunk_z = player.z.floor() as i32 >> 4;
+    let x = chunk_x << 4;
+    let z = chunk_z << 4;
+
+    player.worldedit_set_first_position(x, 0, z);
+    player.worldedit_set_second_position(x + 15, 255, z + 15);
+    player.send_worldedit_message(&format!("Chunk selected: {}, {}", chunk_x, chunk_z));
+}
+
+fn execute_wand(mut ctx: CommandExecuteContext<'_>) {
+    let player = ctx.get_player_mut();
+
+    // Try the selected slot first, then the rest of the hotbar and then the main inventory
+    let selected_slot = player.selected_slot as usize + 36;
+    let slot = std::iter::once(selected_slot)
+        .chain(36..45)
+        .chain(9..36)
+        .find(|slot| player.inventory[*slot].is_none());
+    match slot {
+        Some(slot) => {
+            player.inventory[slot] = Some(ItemStack {
+                item_type: Item::from_id(CONFIG.worldedit_wand_item as u32),
+                count: 1,
+                damage: 0,
+                nbt: None,
+            });
+            player.send_inventory();
+            player.send_worldedit_message("Left click: select pos #1; Right click: select pos #2");
+        }
+        None => player.send_error_message("Your inventory is full."),
+    }
+}
+
+fn execute_toggleeditwand(mut ctx: CommandExecuteContext<'_>) {
+    let player = ctx.get_player_mut();
+    player.worldedit_wand_enabled = !player.worldedit_wand_enabled;
+    if player.worldedit_wand_enabled {
+        player.send_worldedit_message("Edit wand enabled.");
+    } else {
+        player.send_worldedit_message("Edit wand disabled.");
+    }
+}
+
+fn execute_showsel(mut ctx: CommandExecuteContext<'_>) {
+    let player_idx = ctx.player_idx;
+    let player = ctx.get_player_mut();
+    player.worldedit_show_selections = !player.worldedit_show_selections;
+    if !player.worldedit_show_selections {
+        player.send_worldedit_message(
+            "Other players' selections will no longer be shown when they change.",
+        );
+        return;
+    }
+    player.send_worldedit_message("Other players' selections are now shown.");
+
+    let messages: Vec<String> = ctx
+        .plot
+        .players
+        .iter()
+        .enumerate()
+        .filter(|&(i, other)| i != player_idx && other.first_position.is_some())
+        .flat_map(|(_, other)| selection_cui_messages(other))
+        .collect();
+    let player = ctx.get_player_mut();
+    for message in messages {
+        player.worldedit_send_cui(&message);
+    }
+}
+
+fn execute_fixlighting(mut ctx: CommandExecuteContext<'_>) {
+    let operation = worldedit_start_operation(ctx.plot, ctx.player_idx);
+    let mut chunks_relit = 0;
+    for record in &operation.records {
+        let chunk = match ctx.plot.get_chunk(record.chunk_x, record.chunk_z) {
+            Some(chunk) => chunk,
+            None => continue,
+        };
+        let light_data = chunk.encode_light_packet();
+        for player in &mut ctx.plot.players {
+            player.client.send_packet(&light_data);
+        }
+        chunks_relit += 1;
+    }
+
+    ctx.get_player_mut()
+        .send_worldedit_message(&format!("Relit {} chunk(s).", chunks_relit));
+}
+
+fn execute_setbiome(mut ctx: CommandExecuteContext<'_>) {
+    let name = ctx.arguments[0].unwrap_string().clone();
+    let biome = match Biome::id_from_name(&name) {
+        Some(biome) => biome,
+        None => {
+            ctx.get_player_mut()
+                .send_error_message(&format!("Unknown biome: {}", name));
+            return;
+        }
+    };
+
+    let operation = worldedit_start_operation(ctx.plot, ctx.player_idx);
+    let mut changed_chunks = Vec::new();
+    for record in &operation.records {
+        let chunk = match ctx.plot.get_chunk_mut(record.chunk_x, record.chunk_z) {
+            Some(chunk) => chunk,
+            None => continue,
+        };
+        let chunk_start_x = record.chunk_x << 4;
+        let chunk_start_z = record.chunk_z << 4;
+        let x_range = *operation.x_range.start().max(&chunk_start_x)
+            ..=*operation.x_range.end().min(&(chunk_start_x + 15));
+        let z_range = *operation.z_range.start().max(&chunk_start_z)
+            ..=*operation.z_range.end().min(&(chunk_start_z + 15));
+        let mut changed = false;
+        for x in x_range {
+            for z in z_range.clone() {
+                changed |= chunk.set_biome((x & 0xF) as u32, (z & 0xF) as u32, biome);
+            }
+        }
+        if changed {
+            changed_chunks.push((record.chunk_x, record.chunk_z));
+        }
+    }
+    worldedit_send_chunks(ctx.plot, changed_chunks, true);
+
+    let columns = operation.x_range.count() * operation.z_range.count();
+    ctx.get_player_mut().send_worldedit_message(&format!(
+        "Set the biome of {} column(s) to {}.",
+        columns, BIOMES[biome as usize].name
+    ));
+}
+
+fn execute_unimplemented(_ctx: CommandExecuteContext<'_>) {
+    unimplemented!("Unimplimented worldedit command");
+}
+
+#[test]
+fn move_overlapping_region() {
+    let mut cb = WorldEditClipboard {
+        offset_x: 0,
+        offset_y: 0,
+        offset_z: 0,
+        size_x: 7,
+        size_y: 1,
+        size_z: 1,
+        data: PalettedBitBuffer::with_entries(7),
+        block_entities: HashMap::new(),
+    };
+    // A 5 block long row of distinct blocks, followed by 2 blocks of room to move into
+    for x in 0..5 {
+        cb.data.set_entry(x, x as u32 + 1);
+    }
+    cb.shift_region(
+        BlockPos::new(0, 0, 0),
+        BlockPos::new(4, 0, 0),
+        BlockPos::new(2, 0, 0),
+        false,
+    );
+    let row: Vec<u32> = (0..7).map(|x| cb.data.get_entry(x)).collect();
+    assert_eq!(row, vec![0, 0, 1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn parse_position_test() {
+    let player_pos = BlockPos::new(10, 64, -20);
+    assert_eq!(
+        parse_position("1,2,3", player_pos),
+        Some(BlockPos::new(1, 2, 3))
+    );
+    assert_eq!(
+        parse_position("~,~-4,~5", player_pos),
+        Some(BlockPos::new(10, 60, -15))
+    );
+    assert_eq!(parse_position("1,2", player_pos), None);
+    assert_eq!(parse_position("~a,2,3", player_pos), None);
+}
+
+#[test]
+fn smooth_height_map_test() {
+    let flat = vec![5; 9];
+    assert_eq!(smooth_height_map(&flat, 3, 3, 4), flat);
+
+    let mut spike = vec![0; 25];
+    spike[12] = 10;
+    let smoothed = smooth_height_map(&spike, 5, 5, 1);
+    assert!(smoothed[12] < 10);
+    assert!(smoothed[7] > 0);
+    assert_eq!(smoothed[0], 0);
+}
+
+#[test]
+fn parse_radii_test() {
+    assert_eq!(parse_radii("5"), Some([5, 5, 5]));
+    assert_eq!(parse_radii("5,3"), Some([5, 3, 5]));
+    assert_eq!(parse_radii("5,3,8"), Some([5, 3, 8]));
+    assert_eq!(parse_radii("5,3,8,1"), None);
+    assert_eq!(parse_radii("5,,8"), None);
+    assert_eq!(parse_radii("-1"), None);
+}
+
+#[test]
+fn pattern_tokens() {
+    assert!(matches!(
+        WorldEditPattern::from_str("#clipboard"),
+        Err(PatternParseError::EmptyClipboard)
+    ));
+    assert!(matches!(
+        WorldEditPattern::from_str("50%glass,50%#nothing"),
+        Err(PatternParseError::UnknownToken(_))
+    ));
+    let empty = Arc::new(WorldEditClipboard {
+        offset_x: 0,
+        offset_y: 0,
+        offset_z: 0,
+        size_x: 0,
+        size_y: 1,
+        size_z: 1,
+        data: PalettedBitBuffer::with_entries(0),
+        block_entities: HashMap::new(),
+    });
+    assert!(matches!(
+        WorldEditPattern::from_str_with_clipboard("#clipboard", Some(&empty)),
+        Err(PatternParseError::EmptyClipboard)
+    ));
+}
+
+#[test]
+fn pattern_weights() {
+    assert!(matches!(
+        WorldEditPattern::from_str("0%glass"),
+        Err(PatternParseError::ZeroWeight)
+    ));
+    assert!(matches!(
+        WorldEditPattern::from_str("0%glass,0.0%quartz_block"),
+        Err(PatternParseError::ZeroWeight)
+    ));
+    assert!(matches!(
+        WorldEditPattern::from_str(""),
+        Err(PatternParseError::InvalidPattern(_))
+    ));
+
+    let pattern = WorldEditPattern::from_str("0%glass,10%quartz_block").unwrap();
+    for _ in 0..100 {
+        assert_eq!(
+            pattern.pick_at(BlockPos::new(0, 0, 0)),
+            Block::from_name("quartz_block").unwrap()
+        );
+    }
+    let empty = WorldEditPattern {
+        parts: Vec::new(),
+        negated: false,
+    };
+    assert_eq!(empty.pick_at(BlockPos::new(0, 0, 0)), Block::Air {});
+}
+
+#[test]
+fn replace_mappings() {
+    let glass = Block::from_name("glass").unwrap();
+    let quartz = Block::from_name("quartz_block").unwrap();
+    let red_wool = Block::from_name("red_wool").unwrap();
+    let mut repeater = Block::from_name("repeater").unwrap();
+    repeater.set_property("delay", "2");
+
+    let mappings =
+        parse_replace_mappings("glass=quartz_block,repeater[delay=2]=red_wool,glass", None)
+            .unwrap();
+    assert_eq!(mappings.len(), 2);
+    assert!(mappings[0].0.matches(glass));
+    assert_eq!(mappings[0].1.pick_at(BlockPos::new(0, 0, 0)), quartz);
+    assert!(mappings[1].0.matches(repeater));
+    assert!(!mappings[1].0.matches(glass));
+    assert_eq!(mappings[1].1.parts.len(), 2);
+    assert!(mappings[1].1.matches(red_wool));
+
+    assert!(matches!(
+        parse_replace_mappings("glass,quartz_block=glass", None),
+        Err(PatternParseError::MissingMapping(_))
+    ));
+}
+
+#[test]
+fn pattern_wildcards() {
+    let concrete = WorldEditPattern::from_str("*_concrete").unwrap();
+    assert_eq!(concrete.parts.len(), 16);
+    assert!(concrete.matches(Block::from_name("lime_concrete").unwrap()));
+    assert!(!concrete.matches(Block::from_name("lime_terracotta").unwrap()));
+    let weight_sum: f32 = concrete.parts.iter().map(|part| part.weight()).sum();
+    assert!((weight_sum - 1.0).abs() < 0.001);
+
+    let repeaters = WorldEditPattern::from_str("repeater[facing=*]").unwrap();
+    assert_eq!(repeaters.parts.len(), 4);
+    let mut east_repeater = Block::from_name("repeater").unwrap();
+    east_repeater.set_property("facing", "east");
+    assert!(repeaters.matches(east_repeater));
+
+    assert!(matches!(
+        WorldEditPattern::from_str("glass[facing=*]"),
+        Err(PatternParseError::UnknownProperty(_))
+    ));
+    assert!(matches!(
+        WorldEditPattern::from_str("*nothing"),
+        Err(PatternParseError::UnknownBlock(_))
+    ));
+}
+
+#[test]
+fn multi_block_change_sections() {
+    let packets = multi_block_change_packets(vec![(BlockPos::new(-1, 17, 35), 231)]);
+    assert_eq!(packets.len(), 1);
+    let packet = &packets[0];
+    assert_eq!((packet.chunk_x, packet.chunk_y, packet.chunk_z), (-1, 1, 2));
+    assert_eq!(packet.records.len(), 1);
+    let record = &packet.records[0];
+    assert_eq!((record.x, record.y, record.z), (15, 1, 3));
+    assert_eq!(record.block_id, 231);
+
+    let packets = multi_block_change_packets(vec![
+        (BlockPos::new(0, 15, 0), 1),
+        (BlockPos::new(0, 16, 0), 2),
+        (BlockPos::new(15, 0, 15), 3),
+    ]);
+    assert_eq!(packets.len(), 2);
+    assert_eq!(packets[0].records.len(), 2);
+    assert_eq!(packets[1].chunk_y, 1);
+}
+
+#[test]
+fn pattern_matches_states() {
+    let mut powered_repeater = Block::from_name("repeater").unwrap();
+    powered_repeater.set_property("powered", "true");
+
+    let any_repeater = WorldEditPattern::from_str("repeater").unwrap();
+    assert!(any_repeater.matches(powered_repeater));
+    assert!(!any_repeater.matches(Block::from_name("comparator").unwrap()));
+    assert_eq!(
+        any_repeater.pick_at(BlockPos::new(0, 0, 0)),
+        Block::from_name("repeater").unwrap()
+    );
+
+    let delay_3 = WorldEditPattern::from_str("repeater[delay=3]").unwrap();
+    assert!(!delay_3.matches(powered_repeater));
+    let exact = WorldEditPattern::from_str("repeater[powered=true]").unwrap();
+    assert!(exact.matches(powered_repeater));
+}
+
+#[test]
+fn pattern_tags() {
+    let wool = WorldEditPattern::from_str("##wool").unwrap();
+    assert_eq!(wool.parts.len(), 16);
+    assert!(wool.matches(Block::from_name("lime_wool").unwrap()));
+    assert!(!wool.matches(Block::from_name("lime_concrete").unwrap()));
+
+    let mut floor_button = Block::from_name("stone_button").unwrap();
+    floor_button.set_property("face", "floor");
+    let buttons = WorldEditPattern::from_str("##minecraft:buttons[face=floor],glass").unwrap();
+    assert!(buttons.matches(floor_button));
+    assert!(buttons.matches(Block::from_name("glass").unwrap()));
+
+    assert!(matches!(
+        WorldEditPattern::from_str("##nothing"),
+        Err(PatternParseError::UnknownTag(_))
+    ));
+}
+
+#[test]
+fn rotate_and_flip_clipboard() {
+    let mut repeater = Block::from_name("repeater").unwrap();
+    repeater.set_property("facing", "north");
+    let mut cb = WorldEditClipboard {
+        offset_x: 0,
+        offset_y: 0,
+        offset_z: 0,
+        size_x: 2,
+        size_y: 1,
+        size_z: 1,
+        data: PalettedBitBuffer::with_entries(2),
+        block_entities: HashMap::new(),
+    };
+    cb.data.set_entry(1, repeater.get_id());
+
+    // The repeater at x = 1 ends up at z = 1 and faces east
+    let rotated = cb.rotate(1);
+    assert_eq!((rotated.size_x, rotated.size_z), (1, 2));
+    let mut east_repeater = repeater;
+    east_repeater.set_property("facing", "east");
+    assert_eq!(rotated.data.get_entry(1), east_repeater.get_id());
+    assert_eq!(rotated.rotate(3).data.get_entry(1), repeater.get_id());
+
+    let flipped = cb.flip(BlockDirection::East);
+    assert_eq!(flipped.data.get_entry(0), repeater.get_id());
+    let mut south_repeater = repeater;
+    south_repeater.set_property("facing", "south");
+    assert_eq!(
+        cb.flip(BlockDirection::North).data.get_entry(1),
+        south_repeater.get_id()
+    );
+
+    let mut observer = Block::from_name("observer").unwrap();
+    observer.set_property("facing", "up");
+    let mut tower = WorldEditClipboard {
+        size_x: 1,
+        size_y: 2,
+        ..cb.clone()
+    };
+    tower.data.set_entry(0, observer.get_id());
+    tower.data.set_entry(1, 0);
+    let flipped = tower.flip_vertical();
+    observer.set_property("facing", "down");
+    assert_eq!(flipped.data.get_entry(0), 0);
+    assert_eq!(flipped.data.get_entry(1), observer.get_id());
+}
+
+#[test]
+fn trim_clipboard() {
+    let glass = Block::from_name("glass").unwrap().get_id();
+    let mut cb = WorldEditClipboard {
+        offset_x: -1,
+        offset_y: 0,
+        offset_z: 0,
+        size_x: 4,
+        size_y: 3,
+        size_z: 1,
+        data: PalettedBitBuffer::with_entries(12),
+        block_entities: HashMap::new(),
+    };
+    // Glass at (1, 1, 0) and (2, 2, 0), with a block entity in the second one
+    cb.data.set_entry(cb.index(1, 1, 0), glass);
+    cb.data.set_entry(cb.index(2, 2, 0), glass);
+    cb.block_entities.insert(
+        BlockPos::new(2, 2, 0),
+        BlockEntity::Container {
+            comparator_override: 3,
+        },
+    );
+
+    let trimmed = cb.trim().unwrap();
+    assert_eq!((trimmed.size_x, trimmed.size_y, trimmed.size_z), (2, 2, 1));
+    assert_eq!(
+        (trimmed.offset_x, trimmed.offset_y, trimmed.offset_z),
+        (-2, -1, 0)
+    );
+    assert_eq!(trimmed.data.get_entry(trimmed.index(0, 0, 0)), glass);
+    assert_eq!(trimmed.data.get_entry(trimmed.index(1, 1, 0)), glass);
+    assert_eq!(trimmed.data.get_entry(trimmed.index(1, 0, 0)), 0);
+    assert!(matches!(
+        trimmed.block_entities.get(&BlockPos::new(1, 1, 0)),
+        Some(BlockEntity::Container {
+            comparator_override: 3
+        })
+    ));
+
+    let air = WorldEditClipboard {
+        data: PalettedBitBuffer::with_entries(12),
+        block_entities: HashMap::new(),
+        ..cb
+    };
+    assert!(air.trim().is_none());
+}
+
+#[test]
+fn deform_clipboard() {
+    let glass = Block::from_name("glass").unwrap().get_id();
+    let quartz = Block::from_name("quartz_block").unwrap().get_id();
+    let mut cb = WorldEditClipboard {
+        offset_x: 0,
+        offset_y: 0,
+        offset_z: 0,
+        size_x: 3,
+        size_y: 1,
+        size_z: 1,
+        data: PalettedBitBuffer::with_entries(3),
+        block_entities: HashMap::new(),
+    };
+    cb.data.set_entry(0, glass);
+    cb.data.set_entry(2, quartz);
+
+    let mirrored = cb.deform(|pos| BlockPos::new(2 - pos.x, pos.y, pos.z));
+    assert_eq!(mirrored.data.get_entry(0), quartz);
+    assert_eq!(mirrored.data.get_entry(2), glass);
+
+    // Blocks taken from outside of the clipboard are air
+    let shifted = cb.deform(|pos| BlockPos::new(pos.x + 2, pos.y, pos.z));
+    assert_eq!(shifted.data.get_entry(0), quartz);
+    assert_eq!(shifted.data.get_entry(1), 0);
+    assert_eq!(shifted.data.get_entry(2), 0);
+}
+
+#[test]
+fn relative_directions() {
+    use BlockDirection::*;
+    // Looking in each horizontal direction: (left, right, back)
+    let cases = [
+        (
+            North,
+            BlockFacing::West,
+            BlockFacing::East,
+            BlockFacing::South,
+        ),
+        (
+            East,
+            BlockFacing::North,
+            BlockFacing::South,
+            BlockFacing::West,
+        ),
+        (
+            South,
+            BlockFacing::East,
+            BlockFacing::West,
+            BlockFacing::North,
+        ),
+        (
+            West,
+            BlockFacing::South,
+            BlockFacing::North,
+            BlockFacing::East,
+        ),
+    ];
+    for &(direction, left, right, back) in &cases {
+        let facing = direction.block_facing();
+        assert_eq!(
+            relative_direction("forward", facing, direction),
+            Some(facing)
+        );
+        assert_eq!(relative_direction("left", facing, direction), Some(left));
+        assert_eq!(relative_direction("right", facing, direction), Some(right));
+        assert_eq!(relative_direction("back", facing, direction), Some(back));
+    }
+
+    // Left and right stay horizontal when looking down
+    assert_eq!(
+        relative_direction("back", BlockFacing::Down, North),
+        Some(BlockFacing::Up)
+    );
+    assert_eq!(
+        relative_direction("l", BlockFacing::Down, North),
+        Some(BlockFacing::West)
+    );
+    assert_eq!(relative_direction("north", BlockFacing::Down, North), None);
+}
+
+#[test]
+fn stack_copies_tile() {
+    let start_pos = BlockPos::new(10, 5, 20);
+    let size = BlockPos::new(3, 2, 4);
+    let end_pos = start_pos + size - BlockPos::new(1, 1, 1);
+
+    let positions = stack_positions(start_pos, size, BlockFacing::West, 3);
+    assert_eq!(
+        positions,
+        vec![
+            BlockPos::new(7, 5, 20),
+            BlockPos::new(4, 5, 20),
+            BlockPos::new(1, 5, 20)
+        ]
+    );
+    // Each copy ends right before the previous one starts
+    let mut previous_start = start_pos;
+    for pos in positions {
+        assert_eq!(pos.x + size.x, previous_start.x);
+        previous_start = pos;
+    }
+
+    for &direction in &[
+        BlockFacing::North,
+        BlockFacing::South,
+        BlockFacing::East,
+        BlockFacing::West,
+        BlockFacing::Up,
+        BlockFacing::Down,
+    ] {
+        let copy_start = stack_positions(start_pos, size, direction, 1)[0];
+        let copy_end = copy_start + size - BlockPos::new(1, 1, 1);
+        // Overlapping along the axes that don't move and touching along the one that does
+        let gap = (copy_start - end_pos).max(start_pos - copy_end);
+        let touching = [gap.x, gap.y, gap.z].iter().filter(|&&g| g == 1).count();
+        assert_eq!(touching, 1, "{:?}", direction);
+        assert!(gap.x <= 1 && gap.y <= 1 && gap.z <= 1, "{:?}", direction);
+    }
+}
+
+/// A world consisting of the single chunk at 0, 0
+#[cfg(test)]
+struct TestWorld {
+    chunk: Chunk,
+}
+
+#[cfg(test)]
+impl World for TestWorld {
+    fn get_block(&self, pos: BlockPos) -> Block {
+        Block::from_id(self.get_block_raw(pos))
+    }
+
+    fn get_block_raw(&self, pos: BlockPos) -> u32 {
+        self.chunk
+            .get_block(pos.x as u32, pos.y as u32, pos.z as u32)
+    }
+
+    fn set_block(&mut self, pos: BlockPos, block: Block) -> bool {
+        self.set_block_raw(pos, block.get_id())
+    }
+
+    fn set_block_raw(&mut self, pos: BlockPos, block: u32) -> bool {
+        self.chunk
+            .set_block_raw(pos.x as u32, pos.y as u32, pos.z as u32, block)
+    }
+
+    fn delete_block_entity(&mut self, pos: BlockPos) {
+        self.chunk.delete_block_entity(pos);
+    }
+
+    fn get_block_entity(&self, pos: BlockPos) -> Option<&BlockEntity> {
+        self.chunk.get_block_entity(pos)
+    }
+
+    fn set_block_entity(&mut self, pos: BlockPos, block_entity: BlockEntity) {
+        self.chunk.set_block_entity(pos, block_entity);
+    }
+
+    fn get_chunk(&self, x: i32, z: i32) -> Option<&Chunk> {
+        Some(&self.chunk).filter(|_| x == 0 && z == 0)
+    }
+
+    fn get_chunk_mut(&mut self, x: i32, z: i32) -> Option<&mut Chunk> {
+        Some(&mut self.chunk).filter(|_| x == 0 && z == 0)
+    }
+
+    fn tick(&mut self) {}
+
+    fn schedule_tick(
+        &mut self,
+        _pos: BlockPos,
+        _delay: u32,
+        _priority: crate::world::TickPriority,
+    ) {
+    }
+
+    fn pending_tick_at(&mut self, _pos: BlockPos) -> bool {
+        false
+    }
+}
+
+#[test]
+fn set_clears_block_entities() {
+    let mut world = TestWorld {
+        chunk: Chunk::empty(0, 0),
+    };
+    let barrel = Block::from_name("barrel").unwrap().get_id();
+    for x in 0..4 {
+        let pos = BlockPos::new(x, 10, 0);
+        world.set_block_raw(pos, barrel);
+        world.set_block_entity(
+            pos,
+            BlockEntity::Container {
+                comparator_override: 3,
+            },
+        );
+    }
+
+    for x in 0..4 {
+        assert!(set_block_clearing_entity(
+            &mut world,
+            BlockPos::new(x, 10, 0),
+            0
+        ));
+    }
+    assert!(world.chunk.block_entities.is_empty());
+    assert_eq!(world.get_block_raw(BlockPos::new(2, 10, 0)), 0);
+}
+
+#[test]
+fn replace_negated_mask() {
+    let mut world = TestWorld {
+        chunk: Chunk::empty(0, 0),
+    };
+    let glass = Block::from_name("glass").unwrap();
+    let red_wool = Block::from_name("red_wool").unwrap();
+    let quartz = Block::from_name("quartz_block").unwrap();
+    for x in 0..4 {
+        for z in 0..4 {
+            let block = match (x + z) % 3 {
+                0 => Block::Air {},
+                1 => glass,
+                _ => red_wool,
+            };
+            world.set_block(BlockPos::new(x, 10, z), block);
+        }
+    }
+
+    let mappings = vec![(
+        WorldEditPattern::mask_from_str("!air").unwrap(),
+        WorldEditPattern::from_str("quartz_block").unwrap(),
+    )];
+    for x in 0..4 {
+        for z in 0..4 {
+            let pos = BlockPos::new(x, 10, z);
+            if let Some(block) = pick_replacement(&mappings, pos, world.get_block(pos), false) {
+                world.set_block(pos, block);
+            }
+        }
+    }
+    for x in 0..4 {
+        for z in 0..4 {
+            let expected = if (x + z) % 3 == 0 {
+                Block::Air {}
+            } else {
+                quartz
+            };
+            assert_eq!(world.get_block(BlockPos::new(x, 10, z)), expected);
+        }
+    }
+
+    let neither = WorldEditPattern::mask_from_str("!air,glass").unwrap();
+    assert!(neither.matches(red_wool));
+    assert!(!neither.matches(glass));
+    assert!(!neither.matches(Block::Air {}));
+    assert!(WorldEditPattern::from_str("!air").is_err());
+}
+
+#[test]
+fn clipboard_upload_reassembles_parts() {
+    let schematic: Vec<u8> = (0..CLIPBOARD_PART_SIZE * 2 + 5).map(|i| i as u8).collect();
+    let messages = clipboard_messages(&schematic);
+    assert_eq!(messages.len(), 3);
+
+    let mut upload = ClipboardUpload::default();
+    assert_eq!(upload.receive_part(&messages[0]), Ok(None));
+    assert_eq!(upload.receive_part(&messages[1]), Ok(None));
+    assert_eq!(upload.receive_part(&messages[2]), Ok(Some(schematic)));
+
+    // A skipped part can't be recovered from, but a new upload can start afterwards
+    assert_eq!(upload.receive_part(&messages[0]), Ok(None));
+    assert!(upload.receive_part(&messages[2]).is_err());
+    assert_eq!(upload.receive_part(&messages[0]), Ok(None));
+}
+
+#[test]
+fn clipboard_upload_with_malformed_schematics() {
+    use nbt::Value;
+    let upload = |palette_key: &str, block_entity_pos: Vec<i32>| {
+        let mut nbt = nbt::Blob::new();
+        nbt.insert("Version", Value::Int(2)).unwrap();
+        nbt.insert("Width", Value::Short(1)).unwrap();
+        nbt.insert("Height", Value::Short(1)).unwrap();
+        nbt.insert("Length", Value::Short(1)).unwrap();
+        let mut palette = HashMap::new();
+        palette.insert(palette_key.to_owned(), Value::Int(0));
+        nbt.insert("Palette", Value::Compound(palette)).unwrap();
+        nbt.insert("BlockData", Value::ByteArray(vec![0])).unwrap();
+        let mut block_entity = HashMap::new();
+        block_entity.insert("Id".to_owned(), Value::String("minecraft:sign".to_owned()));
+        block_entity.insert("Pos".to_owned(), Value::IntArray(block_entity_pos));
+        nbt.insert(
+            "BlockEntities",
+            Value::List(vec![Value::Compound(block_entity)]),
+        )
+        .unwrap();
+        let mut schematic = Vec::new();
+        nbt.to_gzip_writer(&mut schematic).unwrap();
+
+        let mut upload = ClipboardUpload::default();
+        let mut received = None;
+        for message in clipboard_messages(&schematic) {
+            received = upload.receive_part(&message).unwrap();
+        }
+        WorldEditClipboard::from_schematic_bytes(&received.unwrap())
+    };
+
+    // A property without a value is skipped instead of reading past the end of the properties
+    assert!(upload("minecraft:stone[a]", vec![0, 0, 0]).is_ok());
+    assert!(upload("minecraft:glass", vec![]).is_err());
+}
+
+#[test]
+fn worldedit_permissions() {
+    let permissions = [
+        "pos1".to_owned(),
+        "stack".to_owned(),
+        "set.*".to_owned(),
+        "copy.t".to_owned(),
+    ];
+    assert!(permission_granted(&permissions, "pos1"));
+    assert!(permission_granted(&permissions, "stack"));
+    assert!(!permission_granted(&permissions, "stack.a"));
+    assert!(permission_granted(&permissions, "set"));
+    assert!(permission_granted(&permissions, "set.a"));
+    assert!(!permission_granted(&permissions, "setbiome"));
+    assert!(!permission_granted(&permissions, "copy"));
+    assert!(permission_granted(&permissions, "copy.t"));
+    assert!(permission_granted(&["*".to_owned()], "replace.a"));
+    assert!(!permission_granted(&["*".to_owned()], "upload"));
+    assert!(permission_granted(
+        &["*".to_owned(), "upload".to_owned()],
+        "upload"
+    ));
+}
+
+#[test]
+fn count_command() {
+    let (mut plot, received) = super::test_plot();
+    plot.set_block_raw(
+        BlockPos::new(3, 20, 3),
+        Block::from_name("glass").unwrap().get_id(),
+    );
+    plot.set_block_raw(
+        BlockPos::new(4, 21, 3),
+        Block::from_name("glass").unwrap().get_id(),
+    );
+    plot.players[0].first_position = Some(BlockPos::new(0, 20, 0));
+    plot.players[0].second_position = Some(BlockPos::new(7, 25, 7));
+    assert!(execute_command(&mut plot, 0, "count", &mut vec!["glass"]));
+    assert!(execute_command(&mut plot, 0, "count", &mut vec!["!air"]));
+
+    let messages: Vec<String> = super::test_plot_packets(plot, received)
+        .into_iter()
+        .filter(|(id, _)| *id == 0x0E)
+        .map(|(_, data)| String::from_utf8_lossy(&data).into_owned())
+        .collect();
+    assert_eq!(
+        messages
+            .iter()
+            .filter(|message| message.contains("Counted 2 block(s)"))
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn replace_dry_run_command() {
+    let (mut plot, received) = super::test_plot();
+    let glass = Block::from_name("glass").unwrap().get_id();
+    plot.set_block_raw(BlockPos::new(3, 20, 3), glass);
+    plot.set_block_raw(BlockPos::new(4, 21, 3), glass);
+    plot.players[0].first_position = Some(BlockPos::new(0, 20, 0));
+    plot.players[0].second_position = Some(BlockPos::new(7, 25, 7));
+    assert!(execute_command(
+        &mut plot,
+        0,
+        "replace",
+        &mut vec!["-n", "glass", "red_wool"]
+    ));
+    assert_eq!(plot.get_block_raw(BlockPos::new(3, 20, 3)), glass);
+
+    let messages: Vec<String> = super::test_plot_packets(plot, received)
+        .into_iter()
+        .filter(|(id, _)| *id == 0x0E)
+        .map(|(_, data)| String::from_utf8_lossy(&data).into_owned())
+        .collect();
+    assert!(messages
+        .iter()
+        .any(|message| message.contains("Dry run: 2 block(s) would be changed")));
+}
+
+#[test]
+fn writing_commands_wait_for_queue() {
+    let (mut plot, received) = super::test_plot();
+    // Too many blocks to be set right away, so the job is queued
+    plot.players[0].worldedit_limit = 2_000_000;
+    plot.players[0].first_position = Some(BlockPos::new(0, 0, 0));
+    plot.players[0].second_position = Some(BlockPos::new(255, 15, 255));
+    assert!(execute_command(&mut plot, 0, "set", &mut vec!["glass"]));
+    assert_eq!(plot.worldedit_queue.len(), 1);
+
+    plot.players[0].first_position = Some(BlockPos::new(0, 20, 0));
+    plot.players[0].second_position = Some(BlockPos::new(1, 20, 1));
+    assert!(execute_command(&mut plot, 0, "move", &mut vec!["1"]));
+    assert!(execute_command(
+        &mut plot,
+        0,
+        "rotate",
+        &mut vec!["90", "-s"]
+    ));
+    assert!(execute_command(&mut plot, 0, "set", &mut vec!["glass"]));
+    assert_eq!(plot.worldedit_queue.len(), 1);
+    assert_eq!(plot.players[0].worldedit_undo.len(), 1);
+
+    let messages: Vec<String> = super::test_plot_packets(plot, received)
+        .into_iter()
+        .filter(|(id, _)| *id == 0x0E)
+        .map(|(_, data)| String::from_utf8_lossy(&data).into_owned())
+        .collect();
+    assert_eq!(
+        messages
+            .iter()
+            .filter(|message| message.contains("Wait for the running worldedit operations"))
+            .count(),
+        3
+    );
+}
+
+#[test]
+fn queued_jobs_finish_before_their_player_leaves() {
+    let (mut plot, received) = super::test_plot();
+    plot.players[0].worldedit_limit = 2_000_000;
+    plot.players[0].first_position = Some(BlockPos::new(0, 100, 0));
+    plot.players[0].second_position = Some(BlockPos::new(255, 115, 255));
+    assert!(execute_command(
+        &mut plot,
+        0,
+        "replace",
+        &mut vec!["air", "glass"]
+    ));
+    assert_eq!(plot.worldedit_queue.len(), 1);
+
+    let mut player = plot.leave_plot(0);
+    assert!(plot.worldedit_queue.is_empty());
+    assert_eq!(
+        plot.get_block(BlockPos::new(255, 115, 255)),
+        Block::from_name("glass").unwrap()
+    );
+    // The job recorded its own undo, which the player takes with them
+    assert_eq!(player.worldedit_undo.len(), 1);
+
+    player.client.close_connection();
+    std::mem::forget(player);
+    super::test_plot_packets(plot, received);
+}
+
+#[test]
+fn snapshot_save_limits() {
+    let (mut plot, received) = super::test_plot();
+    plot.players[0].first_position = Some(BlockPos::new(0, 20, 0));
+    plot.players[0].second_position = Some(BlockPos::new(1, 20, 1));
+    let names: Vec<String> = (0..=MAX_SNAPSHOTS).map(|i| format!("s{}", i)).collect();
+    for name in &names {
+        assert!(execute_command(
+            &mut plot,
+            0,
+            "snapshot",
+            &mut vec!["save", name]
+        ));
+    }
+    assert_eq!(plot.worldedit_snapshots.len(), MAX_SNAPSHOTS);
+    // Existing snapshots can still be replaced
+    assert!(execute_command(
+        &mut plot,
+        0,
+        "snapshot",
+        &mut vec!["save", "s0"]
+    ));
+
+    plot.players[0].worldedit_limit = 3;
+    assert!(execute_command(
+        &mut plot,
+        0,
+        "snapshot",
+        &mut vec!["save", "s1"]
+    ));
+    plot.players[0].second_position = Some(BlockPos::new(1, 256, 1));
+    plot.players[0].worldedit_limit = 1000;
+    assert!(execute_command(
+        &mut plot,
+        0,
+        "snapshot",
+        &mut vec!["save", "s1"]
+    ));
+    assert_eq!(plot.worldedit_snapshots["s1"].size_y, 1);
+
+    let messages: Vec<String> = super::test_plot_packets(plot, received)
+        .into_iter()
+        .filter(|(id, _)| *id == 0x0E)
+        .map(|(_, data)| String::from_utf8_lossy(&data).into_owned())
+        .collect();
+    assert!(messages
+        .iter()
+        .any(|message| message.contains("more than 16 snapshots")));
+    assert!(messages
+        .iter()
+        .any(|message| message.contains("over your limit")));
+}
+
+#[test]
+fn move_stays_inside_of_the_plot() {
+    let (mut plot, received) = super::test_plot();
+    plot.players[0].first_position = Some(BlockPos::new(250, 20, 0));
+    plot.players[0].second_position = Some(BlockPos::new(255, 20, 0));
+    let undo_count = plot.players[0].worldedit_undo.len();
+    assert!(execute_command(
+        &mut plot,
+        0,
+        "move",
+        &mut vec!["1", "east"]
+    ));
+    assert!(execute_command(
+        &mut plot,
+        0,
+        "move",
+        &mut vec!["236", "up"]
+    ));
+    assert_eq!(plot.players[0].worldedit_undo.len(), undo_count);
+    assert!(execute_command(
+        &mut plot,
+        0,
+        "move",
+        &mut vec!["1", "west"]
+    ));
+    assert_eq!(plot.players[0].worldedit_undo.len(), undo_count + 1);
+    super::test_plot_packets(plot, received);
+}
+
+#[test]
+fn paste_sends_block_entities() {
+    use crate::blocks::SignBlockEntity;
+
+    let (mut plot, received) = super::test_plot();
+    let sign_pos = BlockPos::new(3, 20, 3);
+    plot.set_block_raw(sign_pos, Block::from_name("oak_sign").unwrap().get_id());
+    plot.set_block_entity(
+        sign_pos,
+        BlockEntity::Sign(Box::new(SignBlockEntity {
+            rows: [
+                r#"{"text":"pasted"}"#.to_owned(),
+                r#"{"text":""}"#.to_owned(),
+                r#"{"text":""}"#.to_owned(),
+                r#"{"text":""}"#.to_owned(),
+            ],
+        })),
+    );
+    plot.players[0].first_position = Some(sign_pos);
+    plot.players[0].second_position = Some(sign_pos);
+    assert!(execute_command(&mut plot, 0, "copy", &mut vec![]));
+    plot.players[0].x += 10.0;
+    assert!(execute_command(&mut plot, 0, "paste", &mut vec![]));
+
+    let pasted_pos = BlockPos::new(13, 20, 3);
+    assert!(matches!(
+        plot.get_block_entity(pasted_pos),
+        Some(BlockEntity::Sign(sign)) if sign.rows[0] == r#"{"text":"pasted"}"#
+    ));
+    // The sign placed for the copy was sent as well, so only the pasted one is looked at
+    let block_entity_data: Vec<Vec<u8>> = super::test_plot_packets(plot, received)
+        .into_iter()
+        .filter(|(id, data)| *id == 0x09 && data.as_slice().read_position().unwrap() == (13, 20, 3))
+        .map(|(_, data)| data)
+        .collect();
+    assert_eq!(block_entity_data.len(), 1);
+    assert!(String::from_utf8_lossy(&block_entity_data[0]).contains("pasted"));
+}
@@ -0,0 +1,955 @@
+use super::WorldEditClipboard;
+use crate::blocks::{Block, BlockEntity, BlockPos};
+use crate::config::CONFIG;
+use crate::network::packets::{PacketDecoderExt, PacketEncoderExt};
+use crate::world::storage::PalettedBitBuffer;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug)]
+pub enum SchematicLoadError {
+    InvalidPath,
+    NotFound,
+    Io(io::Error),
+    InvalidNbt(nbt::Error),
+    UnknownFormat,
+    UnsupportedVersion(i32),
+    InvalidSchematic,
+    TooLarge(u64),
+}
+
+impl fmt::Display for SchematicLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchematicLoadError::InvalidPath => {
+                write!(f, "schematic names can't be absolute or contain '..'")
+            }
+            SchematicLoadError::NotFound => write!(f, "the schematic file could not be found"),
+            SchematicLoadError::Io(err) => {
+                write!(f, "the schematic file could not be read: {}", err)
+            }
+            SchematicLoadError::InvalidNbt(err) => {
+                write!(f, "the schematic file is not valid NBT: {}", err)
+            }
+            SchematicLoadError::UnknownFormat => {
+                write!(f, "the schematic is not in the Sponge or MCEdit format")
+            }
+            SchematicLoadError::UnsupportedVersion(version) => write!(
+                f,
+                "version {} Sponge schematics are not supported, only versions 1 and 2 are",
+                version
+            ),
+            SchematicLoadError::InvalidSchematic => {
+                write!(f, "the schematic is missing data or is corrupted")
+            }
+            SchematicLoadError::TooLarge(volume) => write!(
+                f,
+                "the schematic has {} blocks, which is more than the limit of {}",
+                volume, CONFIG.worldedit_max_limit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SchematicLoadError {}
+
+pub type SchematicLoadResult<T> = std::result::Result<T, SchematicLoadError>;
+
+impl WorldEditClipboard {
+    /// Loads a schematic from the player's own schematic folder (`<schems_dir>/<uuid>/`) or,
+    /// if it isn't there, from the shared `schems_dir` folder. Names may contain
+    /// subdirectories such as `<uuid>/<name>`.
+    pub(super) fn load_from_schematic(
+        schems_dir: &Path,
+        file_name: &str,
+        player_uuid: u128,
+    ) -> SchematicLoadResult<WorldEditClipboard> {
+        let relative_path = schematic_path(file_name).ok_or(SchematicLoadError::InvalidPath)?;
+        let player_path = schems_dir
+            .join(format!("{:032x}", player_uuid))
+            .join(&relative_path);
+        let path = if player_path.is_file() {
+            player_path
+        } else {
+            schems_dir.join(relative_path)
+        };
+        let data = fs::read(path).map_err(|err| {
+            if err.kind() == io::ErrorKind::NotFound {
+                SchematicLoadError::NotFound
+            } else {
+                SchematicLoadError::Io(err)
+            }
+        })?;
+        WorldEditClipboard::from_schematic_bytes(&data)
+    }
+
+    /// Loads a clipboard from the contents of a schematic file.
+    pub(super) fn from_schematic_bytes(data: &[u8]) -> SchematicLoadResult<WorldEditClipboard> {
+        // Schematics are supposed to be gzipped, but uncompressed ones are accepted as well.
+        // Only so much is decompressed, since a tiny file can unpack to gigabytes.
+        let mut decompressed = Vec::new();
+        let nbt_data = match flate2::read::GzDecoder::new(data)
+            .take(MAX_SCHEMATIC_NBT_SIZE)
+            .read_to_end(&mut decompressed)
+        {
+            Ok(_) => &decompressed[..],
+            Err(_) => data,
+        };
+        if check_nbt_lengths(nbt_data).is_none() {
+            return Err(SchematicLoadError::InvalidSchematic);
+        }
+        let nbt =
+            nbt::Blob::from_reader(&mut &nbt_data[..]).map_err(SchematicLoadError::InvalidNbt)?;
+        WorldEditClipboard::from_schematic_nbt(&nbt)
+    }
+
+    /// Saves the clipboard as a gzipped version 2 Sponge schematic.
+    pub(super) fn to_schematic_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.to_schematic_nbt().to_gzip_writer(&mut data).unwrap();
+        data
+    }
+
+    /// Blocks without a name (the ones `Block::Unknown` stands for) are saved as air, since
+    /// the server doesn't know what they're called. Containers are left out because only
+    /// their comparator output is known, not their items.
+    fn to_schematic_nbt(&self) -> nbt::Blob {
+        use nbt::Value;
+        let mut palette: HashMap<String, Value> = HashMap::new();
+        // Looking up the name of a block is slow, so it's only done once for every block id
+        let mut palette_ids: HashMap<u32, i32> = HashMap::new();
+        let mut block_data = Vec::new();
+        let volume = (self.size_x * self.size_y * self.size_z) as usize;
+        for idx in 0..volume {
+            let entry = self.data.get_entry(idx);
+            let palette_id = *palette_ids.entry(entry).or_insert_with(|| {
+                let next_id = palette.len() as i32;
+                let state = block_state_name(Block::from_id(entry));
+                match palette.entry(state).or_insert(Value::Int(next_id)) {
+                    Value::Int(id) => *id,
+                    _ => unreachable!(),
+                }
+            });
+            block_data.write_varint(palette_id);
+        }
+
+        let block_entities = self
+            .block_entities
+            .iter()
+            .filter_map(|(pos, block_entity)| block_entity_nbt(*pos, block_entity))
+            .collect();
+        // The top level `Offset` is where the region was in the world, which the clipboard
+        // doesn't know, so it is left out.
+        let mut metadata = HashMap::new();
+        metadata.insert("WEOffsetX".to_owned(), Value::Int(-self.offset_x));
+        metadata.insert("WEOffsetY".to_owned(), Value::Int(-self.offset_y));
+        metadata.insert("WEOffsetZ".to_owned(), Value::Int(-self.offset_z));
+
+        let mut nbt = nbt::Blob::new();
+        let _ = nbt.insert("Version", Value::Int(2));
+        let _ = nbt.insert("DataVersion", Value::Int(DATA_VERSION));
+        let _ = nbt.insert("Width", Value::Short(self.size_x as i16));
+        let _ = nbt.insert("Height", Value::Short(self.size_y as i16));
+        let _ = nbt.insert("Length", Value::Short(self.size_z as i16));
+        let _ = nbt.insert("Metadata", Value::Compound(metadata));
+        let _ = nbt.insert("PaletteMax", Value::Int(palette.len() as i32));
+        let _ = nbt.insert("Palette", Value::Compound(palette));
+        let _ = nbt.insert(
+            "BlockData",
+            Value::ByteArray(block_data.into_iter().map(|b| b as i8).collect()),
+        );
+        let _ = nbt.insert("BlockEntities", Value::List(block_entities));
+        nbt
+    }
+
+    fn from_schematic_nbt(nbt: &nbt::Blob) -> SchematicLoadResult<WorldEditClipboard> {
+        // Sponge schematics store their blocks in `BlockData` while the legacy
+        // MCEdit format uses `Blocks` and `Data`.
+        let clipboard = if nbt.get("BlockData").is_some() {
+            let version = match nbt.get("Version") {
+                Some(nbt::Value::Int(version)) => *version,
+                _ => 1,
+            };
+            if !(1..=2).contains(&version) {
+                return Err(SchematicLoadError::UnsupportedVersion(version));
+            }
+            check_schematic_size(nbt)?;
+            load_sponge_schematic(nbt, version)
+        } else if nbt.get("Blocks").is_some() {
+            check_schematic_size(nbt)?;
+            load_legacy_schematic(nbt)
+        } else {
+            return Err(SchematicLoadError::UnknownFormat);
+        };
+        let mut clipboard = clipboard.ok_or(SchematicLoadError::InvalidSchematic)?;
+        // Block entities outside of the schematic's blocks don't belong to any of them
+        let block_entities = std::mem::take(&mut clipboard.block_entities);
+        clipboard.block_entities = block_entities
+            .into_iter()
+            .filter(|(pos, _)| clipboard.contains(*pos))
+            .collect();
+        Ok(clipboard)
+    }
+}
+
+/// The most that is decompressed from a gzipped schematic
+const MAX_SCHEMATIC_NBT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Walks through NBT data without reading it, to make sure that no array or list claims to be
+/// longer than the data that's left and that compounds and lists aren't nested too deeply.
+/// The NBT reader allocates whatever length it reads up front and reads nested tags
+/// recursively, so data like that could crash the server.
+fn check_nbt_lengths(mut data: &[u8]) -> Option<()> {
+    use byteorder::{BigEndian, ReadBytesExt};
+    use std::convert::TryFrom;
+
+    fn skip(data: &mut &[u8], len: usize) -> Option<()> {
+        *data = data.get(len..)?;
+        Some(())
+    }
+
+    fn read_len(data: &mut &[u8], element_size: usize) -> Option<usize> {
+        let len = usize::try_from(data.read_i32::<BigEndian>().ok()?).ok()?;
+        if len.checked_mul(element_size)? > data.len() {
+            return None;
+        }
+        Some(len)
+    }
+
+    fn check_payload(data: &mut &[u8], tag: u8, depth: usize) -> Option<()> {
+        if depth > 512 {
+            return None;
+        }
+        match tag {
+            1 => skip(data, 1),
+            2 => skip(data, 2),
+            3 | 5 => skip(data, 4),
+            4 | 6 => skip(data, 8),
+            7 => {
+                let len = read_len(data, 1)?;
+                skip(data, len)
+            }
+            8 => {
+                let len = data.read_u16::<BigEndian>().ok()? as usize;
+                skip(data, len)
+            }
+            9 => {
+                let element_tag = data.read_u8().ok()?;
+                let len = read_len(data, 1)?;
+                // Lists of end tags take up no space, so they have to be empty
+                if element_tag == 0 && len > 0 {
+                    return None;
+                }
+                for _ in 0..len {
+                    check_payload(data, element_tag, depth + 1)?;
+                }
+                Some(())
+            }
+            10 => loop {
+                let tag = data.read_u8().ok()?;
+                if tag == 0 {
+                    return Some(());
+                }
+                let name_len = data.read_u16::<BigEndian>().ok()? as usize;
+                skip(data, name_len)?;
+                check_payload(data, tag, depth + 1)?;
+            },
+            11 => {
+                let len = read_len(data, 4)?;
+                skip(data, len * 4)
+            }
+            12 => {
+                let len = read_len(data, 8)?;
+                skip(data, len * 8)
+            }
+            _ => None,
+        }
+    }
+
+    // The root compound has a name like every other tag
+    if data.read_u8().ok()? != 10 {
+        return None;
+    }
+    let name_len = data.read_u16::<BigEndian>().ok()? as usize;
+    skip(&mut data, name_len)?;
+    check_payload(&mut data, 10, 0)
+}
+
+/// Both formats store their size the same way. Schematics without any blocks or with more
+/// than `worldedit_max_limit` blocks are rejected before anything is allocated for them.
+fn check_schematic_size(nbt: &nbt::Blob) -> SchematicLoadResult<()> {
+    let size = |key| match nbt.get(key) {
+        Some(nbt::Value::Short(size)) if *size > 0 => Ok(*size as u64),
+        _ => Err(SchematicLoadError::InvalidSchematic),
+    };
+    let volume = size("Width")? * size("Height")? * size("Length")?;
+    if volume > CONFIG.worldedit_max_limit.max(0) as u64 {
+        return Err(SchematicLoadError::TooLarge(volume));
+    }
+    Ok(())
+}
+
+/// The data version of Minecraft 1.16.4, which saved schematics are made for
+const DATA_VERSION: i32 = 2584;
+
+/// The block state as it is written in schematic palettes, like `minecraft:lever[face=wall]`
+fn block_state_name(block: Block) -> String {
+    let name = match block.get_name() {
+        Some(name) => name,
+        None => return "minecraft:air".to_owned(),
+    };
+    let mut properties = block.properties();
+    if properties.is_empty() {
+        return format!("minecraft:{}", name);
+    }
+    properties.sort_unstable();
+    let properties: Vec<String> = properties
+        .into_iter()
+        .map(|(key, val)| format!("{}={}", key, val))
+        .collect();
+    format!("minecraft:{}[{}]", name, properties.join(","))
+}
+
+fn block_entity_nbt(pos: BlockPos, block_entity: &BlockEntity) -> Option<nbt::Value> {
+    use nbt::Value;
+    let mut nbt = HashMap::new();
+    match block_entity {
+        BlockEntity::Comparator { output_strength } => {
+            nbt.insert(
+                "Id".to_owned(),
+                Value::String("minecraft:comparator".to_owned()),
+            );
+            nbt.insert(
+                "OutputSignal".to_owned(),
+                Value::Int(*output_strength as i32),
+            );
+        }
+        BlockEntity::Sign(sign) => {
+            nbt.insert("Id".to_owned(), Value::String("minecraft:sign".to_owned()));
+            for (i, row) in sign.rows.iter().enumerate() {
+                nbt.insert(format!("Text{}", i + 1), Value::String(row.clone()));
+            }
+        }
+        BlockEntity::Container { .. } => return None,
+    }
+    nbt.insert("Pos".to_owned(), Value::IntArray(vec![pos.x, pos.y, pos.z]));
+    Some(Value::Compound(nbt))
+}
+
+/// Checks that a schematic name stays inside of the schematic folder. Absolute paths and
+/// paths containing `..` are rejected.
+fn schematic_path(file_name: &str) -> Option<PathBuf> {
+    let path = Path::new(file_name);
+    let is_relative = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if file_name.is_empty() || !is_relative {
+        return None;
+    }
+    Some(path.to_path_buf())
+}
+
+/// Lists the names of the schematics the player can load, from both their own schematic
+/// folder and the shared one.
+pub(super) fn list_schematics(schems_dir: &Path, player_uuid: u128) -> Vec<String> {
+    let mut names = Vec::new();
+    for dir in &[
+        schems_dir.join(format!("{:032x}", player_uuid)),
+        schems_dir.to_path_buf(),
+    ] {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            if !entry.path().is_file() {
+                continue;
+            }
+            if let Some(name) = entry.file_name().to_str() {
+                names.push(name.to_owned());
+            }
+        }
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Used for optional int tags which default to zero when they're missing.
+fn get_int_or_zero(value: Option<&nbt::Value>) -> i32 {
+    match value {
+        Some(nbt::Value::Int(val)) => *val,
+        _ => 0,
+    }
+}
+
+fn load_sponge_schematic(nbt: &nbt::Blob, version: i32) -> Option<WorldEditClipboard> {
+    use nbt::Value;
+    let size_x = *nbt_unwrap_val!(nbt.get("Width")?, Value::Short) as u32;
+    let size_z = *nbt_unwrap_val!(nbt.get("Length")?, Value::Short) as u32;
+    let size_y = *nbt_unwrap_val!(nbt.get("Height")?, Value::Short) as u32;
+    let nbt_palette = nbt_unwrap_val!(nbt.get("Palette")?, Value::Compound);
+    // WorldEdit keeps the offset to where the region was copied from in the metadata. The top
+    // level offset that version 2 added is where WorldEdit found the region in the world, so it
+    // is only used for schematics without WorldEdit's offset.
+    let (offset_x, offset_y, offset_z) = match (nbt.get("Metadata"), nbt.get("Offset")) {
+        (Some(Value::Compound(metadata)), _) if metadata.contains_key("WEOffsetX") => (
+            -get_int_or_zero(metadata.get("WEOffsetX")),
+            -get_int_or_zero(metadata.get("WEOffsetY")),
+            -get_int_or_zero(metadata.get("WEOffsetZ")),
+        ),
+        (_, Some(Value::IntArray(offset))) if version == 2 && offset.len() == 3 => {
+            (-offset[0], -offset[1], -offset[2])
+        }
+        _ => (0, 0, 0),
+    };
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"minecraft:([a-z_]+)(?:\[([a-z=,0-9]+)\])?").unwrap();
+    }
+    let mut palette: HashMap<u32, u32> = HashMap::new();
+    for (k, v) in nbt_palette {
+        let id = *nbt_unwrap_val!(v, Value::Int) as u32;
+        let captures = RE.captures(&k)?;
+        let mut block = Block::from_name(captures.get(1)?.as_str()).unwrap_or(Block::Air {});
+        if let Some(properties_match) = captures.get(2) {
+            // Properties without a value are skipped like properties the block doesn't have
+            for (name, value) in properties_match
+                .as_str()
+                .split(',')
+                .filter_map(|property| property.split_once('='))
+            {
+                block.set_property(name, value);
+            }
+        }
+        palette.insert(id, block.get_id());
+    }
+    let blocks: Vec<u8> = nbt_unwrap_val!(nbt.get("BlockData")?, Value::ByteArray)
+        .iter()
+        .map(|b| *b as u8)
+        .collect();
+    let mut data = PalettedBitBuffer::with_entries((size_x * size_y * size_z) as usize);
+    // Reading past the end of the block data or finding an id that isn't in the palette means
+    // the schematic is malformed.
+    let mut reader = &blocks[..];
+    for y_offset in (0..size_y).map(|y| y * size_z * size_x) {
+        for z_offset in (0..size_z).map(|z| z * size_x) {
+            for x in 0..size_x {
+                let blockstate_id = reader.read_varint().ok()? as u32;
+                let entry = *palette.get(&blockstate_id)?;
+                data.set_entry((y_offset + z_offset + x) as usize, entry);
+            }
+        }
+    }
+    // Version 1 called these tile entities. They're optional, so a schematic without any is fine.
+    let block_entities = match nbt.get("BlockEntities").or_else(|| nbt.get("TileEntities")) {
+        Some(Value::List(block_entities)) => block_entities.as_slice(),
+        _ => &[],
+    };
+    let mut parsed_block_entities = HashMap::new();
+    for block_entity in block_entities {
+        let val = nbt_unwrap_val!(block_entity, Value::Compound);
+        let pos_array = nbt_unwrap_val!(val.get("Pos")?, Value::IntArray);
+        if pos_array.len() != 3 {
+            return None;
+        }
+        let pos = BlockPos {
+            x: pos_array[0],
+            y: pos_array[1],
+            z: pos_array[2],
+        };
+        if let Some(parsed) = BlockEntity::from_nbt(val) {
+            parsed_block_entities.insert(pos, parsed);
+        }
+    }
+    Some(WorldEditClipboard {
+        size_x,
+        size_y,
+        size_z,
+        offset_x,
+        offset_y,
+        offset_z,
+        data,
+        block_entities: parsed_block_entities,
+    })
+}
+
+fn load_legacy_schematic(nbt: &nbt::Blob) -> Option<WorldEditClipboard> {
+    use nbt::Value;
+    let size_x = *nbt_unwrap_val!(nbt.get("Width")?, Value::Short) as u32;
+    let size_z = *nbt_unwrap_val!(nbt.get("Length")?, Value::Short) as u32;
+    let size_y = *nbt_unwrap_val!(nbt.get("Height")?, Value::Short) as u32;
+    // WorldEdit writes its offset into the root compound, MCEdit itself doesn't write one at all.
+    let offset_x = -get_int_or_zero(nbt.get("WEOffsetX"));
+    let offset_y = -get_int_or_zero(nbt.get("WEOffsetY"));
+    let offset_z = -get_int_or_zero(nbt.get("WEOffsetZ"));
+
+    let blocks = nbt_unwrap_val!(nbt.get("Blocks")?, Value::ByteArray);
+    let block_data = nbt_unwrap_val!(nbt.get("Data")?, Value::ByteArray);
+    let volume = (size_x * size_y * size_z) as usize;
+    if blocks.len() < volume || block_data.len() < volume {
+        return None;
+    }
+    let mut data = PalettedBitBuffer::with_entries(volume);
+    for i in 0..volume {
+        let block = legacy_block(blocks[i] as u8, block_data[i] as u8 & 0xF);
+        data.set_entry(i, block.get_id());
+    }
+
+    let mut parsed_block_entities = HashMap::new();
+    if let Some(Value::List(block_entities)) = nbt.get("TileEntities") {
+        for block_entity in block_entities {
+            let val = nbt_unwrap_val!(block_entity, Value::Compound);
+            let pos = BlockPos {
+                x: *nbt_unwrap_val!(val.get("x")?, Value::Int),
+                y: *nbt_unwrap_val!(val.get("y")?, Value::Int),
+                z: *nbt_unwrap_val!(val.get("z")?, Value::Int),
+            };
+            if let Some(parsed) = BlockEntity::from_nbt(val) {
+                parsed_block_entities.insert(pos, parsed);
+            }
+        }
+    }
+
+    Some(WorldEditClipboard {
+        size_x,
+        size_y,
+        size_z,
+        offset_x,
+        offset_y,
+        offset_z,
+        data,
+        block_entities: parsed_block_entities,
+    })
+}
+
+/// Converts a block id and data value from before the 1.13 flattening into a block.
+/// Blocks that this server doesn't know about are turned into air.
+fn legacy_block(id: u8, data: u8) -> Block {
+    const COLORS: [&str; 16] = [
+        "white",
+        "orange",
+        "magenta",
+        "light_blue",
+        "yellow",
+        "lime",
+        "pink",
+        "gray",
+        "light_gray",
+        "cyan",
+        "purple",
+        "blue",
+        "brown",
+        "green",
+        "red",
+        "black",
+    ];
+    // Diodes and tripwire hooks use the horizontal index of their facing as data
+    const HORIZONTAL: [&str; 4] = ["south", "west", "north", "east"];
+    // Torches and buttons use the direction they are pointing in
+    const ATTACHED: [&str; 4] = ["east", "west", "south", "north"];
+
+    let color = COLORS[data as usize];
+    let horizontal = HORIZONTAL[(data & 3) as usize];
+    let bit = |mask: u8| (data & mask != 0).to_string();
+
+    let (name, properties) = match id {
+        20 => ("glass".to_owned(), vec![]),
+        24 => ("sandstone".to_owned(), vec![]),
+        35 => (format!("{}_wool", color), vec![]),
+        44 if data & 7 == 7 => ("quartz_slab".to_owned(), vec![]),
+        44 => ("smooth_stone_slab".to_owned(), vec![]),
+        55 => (
+            "redstone_wire".to_owned(),
+            vec![("power", data.to_string())],
+        ),
+        61 | 62 => ("furnace".to_owned(), vec![]),
+        63 => ("oak_sign".to_owned(), vec![("rotation", data.to_string())]),
+        68 => {
+            let facing = match data {
+                3 => "south",
+                4 => "west",
+                5 => "east",
+                _ => "north",
+            };
+            (
+                "oak_wall_sign".to_owned(),
+                vec![("facing", facing.to_owned())],
+            )
+        }
+        69 => {
+            let (face, facing) = match data & 7 {
+                0 => ("ceiling", "west"),
+                5 => ("floor", "north"),
+                6 => ("floor", "west"),
+                7 => ("ceiling", "north"),
+                side => ("wall", ATTACHED[side as usize - 1]),
+            };
+            (
+                "lever".to_owned(),
+                vec![
+                    ("face", face.to_owned()),
+                    ("facing", facing.to_owned()),
+                    ("powered", bit(8)),
+                ],
+            )
+        }
+        70 => ("stone_pressure_plate".to_owned(), vec![]),
+        75 | 76 => {
+            let lit = (id == 76).to_string();
+            match data {
+                1..=4 => (
+                    "redstone_wall_torch".to_owned(),
+                    vec![
+                        ("lit", lit),
+                        ("facing", ATTACHED[data as usize - 1].to_owned()),
+                    ],
+                ),
+                _ => ("redstone_torch".to_owned(), vec![("lit", lit)]),
+            }
+        }
+        77 => {
+            let (face, facing) = match data & 7 {
+                1..=4 => ("wall", ATTACHED[(data & 7) as usize - 1]),
+                0 => ("ceiling", "north"),
+                _ => ("floor", "north"),
+            };
+            (
+                "stone_button".to_owned(),
+                vec![
+                    ("face", face.to_owned()),
+                    ("facing", facing.to_owned()),
+                    ("powered", bit(8)),
+                ],
+            )
+        }
+        93 | 94 => (
+            "repeater".to_owned(),
+            vec![
+                ("facing", horizontal.to_owned()),
+                ("delay", ((data >> 2) + 1).to_string()),
+                ("powered", (id == 94).to_string()),
+            ],
+        ),
+        95 => (format!("{}_stained_glass", color), vec![]),
+        123 | 124 => (
+            "redstone_lamp".to_owned(),
+            vec![("lit", (id == 124).to_string())],
+        ),
+        131 => (
+            "tripwire_hook".to_owned(),
+            vec![("facing", horizontal.to_owned())],
+        ),
+        149 | 150 => {
+            let mode = if data & 4 != 0 { "subtract" } else { "compare" };
+            (
+                "comparator".to_owned(),
+                vec![
+                    ("facing", horizontal.to_owned()),
+                    ("mode", mode.to_owned()),
+                    ("powered", bit(8)),
+                ],
+            )
+        }
+        152 => ("redstone_block".to_owned(), vec![]),
+        154 => ("hopper".to_owned(), vec![]),
+        155 => ("quartz_block".to_owned(), vec![]),
+        159 => (format!("{}_terracotta", color), vec![]),
+        172 => ("terracotta".to_owned(), vec![]),
+        218 => {
+            const FACINGS: [&str; 6] = ["down", "up", "north", "south", "west", "east"];
+            let facing = FACINGS.get((data & 7) as usize).unwrap_or(&"down");
+            (
+                "observer".to_owned(),
+                vec![("facing", (*facing).to_owned())],
+            )
+        }
+        251 => (format!("{}_concrete", color), vec![]),
+        _ => return Block::Air {},
+    };
+
+    let mut block = Block::from_name(&name).unwrap_or(Block::Air {});
+    for (key, val) in properties {
+        block.set_property(key, &val);
+    }
+    block
+}
+
+#[test]
+fn legacy_block_mapping() {
+    assert_eq!(legacy_block(35, 14), Block::from_name("red_wool").unwrap());
+    let mut repeater = Block::from_name("repeater").unwrap();
+    repeater.set_property("facing", "west");
+    repeater.set_property("delay", "3");
+    assert_eq!(legacy_block(93, 0b1001), repeater);
+    assert_eq!(legacy_block(1, 0), Block::Air {});
+}
+
+#[test]
+fn load_sponge_schematic_without_optional_keys() {
+    use nbt::Value;
+    let mut nbt = nbt::Blob::new();
+    nbt.insert("Version", Value::Int(2)).unwrap();
+    nbt.insert("Width", Value::Short(2)).unwrap();
+    nbt.insert("Height", Value::Short(1)).unwrap();
+    nbt.insert("Length", Value::Short(1)).unwrap();
+    let mut palette = HashMap::new();
+    palette.insert("minecraft:air".to_owned(), Value::Int(0));
+    palette.insert("minecraft:glass".to_owned(), Value::Int(1));
+    nbt.insert("Palette", Value::Compound(palette)).unwrap();
+    nbt.insert("BlockData", Value::ByteArray(vec![0, 1]))
+        .unwrap();
+
+    let cb = WorldEditClipboard::from_schematic_nbt(&nbt).unwrap();
+    assert_eq!((cb.offset_x, cb.offset_y, cb.offset_z), (0, 0, 0));
+    assert_eq!((cb.size_x, cb.size_y, cb.size_z), (2, 1, 1));
+    assert!(cb.block_entities.is_empty());
+    assert_eq!(cb.data.get_entry(0), 0);
+    assert_eq!(cb.data.get_entry(1), Block::Glass {}.get_id());
+}
+
+#[test]
+fn save_and_load_sponge_schematic() {
+    let mut repeater = Block::from_name("repeater").unwrap();
+    repeater.set_property("facing", "west");
+    repeater.set_property("delay", "3");
+    let blocks = [
+        Block::Air {},
+        Block::from_name("glass").unwrap(),
+        repeater,
+        Block::from_name("oak_sign").unwrap(),
+    ];
+    let mut data = PalettedBitBuffer::with_entries(blocks.len());
+    for (idx, block) in blocks.iter().enumerate() {
+        data.set_entry(idx, block.get_id());
+    }
+    let mut block_entities = HashMap::new();
+    block_entities.insert(
+        BlockPos::new(1, 1, 0),
+        BlockEntity::Sign(Box::new(crate::blocks::SignBlockEntity {
+            rows: [
+                r#"{"text":"one"}"#.to_owned(),
+                r#"{"text":""}"#.to_owned(),
+                r#"{"text":""}"#.to_owned(),
+                r#"{"text":"four"}"#.to_owned(),
+            ],
+        })),
+    );
+    let cb = WorldEditClipboard {
+        offset_x: -1,
+        offset_y: 0,
+        offset_z: 2,
+        size_x: 2,
+        size_y: 2,
+        size_z: 1,
+        data,
+        block_entities,
+    };
+
+    let loaded = WorldEditClipboard::from_schematic_bytes(&cb.to_schematic_bytes()).unwrap();
+    assert_eq!(
+        (loaded.offset_x, loaded.offset_y, loaded.offset_z),
+        (-1, 0, 2)
+    );
+    assert_eq!((loaded.size_x, loaded.size_y, loaded.size_z), (2, 2, 1));
+    for (idx, block) in blocks.iter().enumerate() {
+        assert_eq!(loaded.data.get_entry(idx), block.get_id());
+    }
+    assert!(matches!(
+        loaded.block_entities.get(&BlockPos::new(1, 1, 0)),
+        Some(BlockEntity::Sign(sign)) if sign.rows[3] == r#"{"text":"four"}"#
+    ));
+}
+
+#[test]
+fn load_sponge_schematic_offset_from_metadata() {
+    use nbt::Value;
+    let mut nbt = nbt::Blob::new();
+    nbt.insert("Version", Value::Int(2)).unwrap();
+    nbt.insert("Width", Value::Short(1)).unwrap();
+    nbt.insert("Height", Value::Short(1)).unwrap();
+    nbt.insert("Length", Value::Short(1)).unwrap();
+    let mut palette = HashMap::new();
+    palette.insert("minecraft:glass".to_owned(), Value::Int(0));
+    nbt.insert("Palette", Value::Compound(palette)).unwrap();
+    nbt.insert("BlockData", Value::ByteArray(vec![0])).unwrap();
+    // WorldEdit writes where the region was in the world as the top level offset
+    nbt.insert("Offset", Value::IntArray(vec![1000, 64, -2000]))
+        .unwrap();
+    let mut metadata = HashMap::new();
+    metadata.insert("WEOffsetX".to_owned(), Value::Int(-1));
+    metadata.insert("WEOffsetY".to_owned(), Value::Int(0));
+    metadata.insert("WEOffsetZ".to_owned(), Value::Int(-2));
+    nbt.insert("Metadata", Value::Compound(metadata)).unwrap();
+
+    let cb = WorldEditClipboard::from_schematic_nbt(&nbt).unwrap();
+    assert_eq!((cb.offset_x, cb.offset_y, cb.offset_z), (1, 0, 2));
+}
+
+#[test]
+fn load_sponge_schematic_with_truncated_block_data() {
+    use nbt::Value;
+    let mut nbt = nbt::Blob::new();
+    nbt.insert("Version", Value::Int(2)).unwrap();
+    nbt.insert("Width", Value::Short(2)).unwrap();
+    nbt.insert("Height", Value::Short(1)).unwrap();
+    nbt.insert("Length", Value::Short(1)).unwrap();
+    let mut palette = HashMap::new();
+    palette.insert("minecraft:air".to_owned(), Value::Int(0));
+    palette.insert("minecraft:glass".to_owned(), Value::Int(200));
+    nbt.insert("Palette", Value::Compound(palette)).unwrap();
+    // The varint for 200 takes two bytes, so the second block is cut off halfway
+    nbt.insert("BlockData", Value::ByteArray(vec![0, -56]))
+        .unwrap();
+
+    assert!(matches!(
+        WorldEditClipboard::from_schematic_nbt(&nbt),
+        Err(SchematicLoadError::InvalidSchematic)
+    ));
+}
+
+#[test]
+fn load_sponge_schematic_with_malformed_entries() {
+    use nbt::Value;
+    let schematic = |palette_key: &str, block_entity_pos: Vec<i32>| {
+        let mut nbt = nbt::Blob::new();
+        nbt.insert("Version", Value::Int(2)).unwrap();
+        nbt.insert("Width", Value::Short(1)).unwrap();
+        nbt.insert("Height", Value::Short(1)).unwrap();
+        nbt.insert("Length", Value::Short(1)).unwrap();
+        let mut palette = HashMap::new();
+        palette.insert(palette_key.to_owned(), Value::Int(0));
+        nbt.insert("Palette", Value::Compound(palette)).unwrap();
+        nbt.insert("BlockData", Value::ByteArray(vec![0])).unwrap();
+        let mut block_entity = HashMap::new();
+        block_entity.insert("Id".to_owned(), Value::String("minecraft:sign".to_owned()));
+        block_entity.insert("Pos".to_owned(), Value::IntArray(block_entity_pos));
+        nbt.insert(
+            "BlockEntities",
+            Value::List(vec![Value::Compound(block_entity)]),
+        )
+        .unwrap();
+        nbt
+    };
+
+    // A property without a value is skipped
+    let nbt = schematic("minecraft:repeater[delay=2,a]", vec![0, 0, 0]);
+    let cb = WorldEditClipboard::from_schematic_nbt(&nbt).unwrap();
+    let mut repeater = Block::from_name("repeater").unwrap();
+    repeater.set_property("delay", "2");
+    assert_eq!(cb.data.get_entry(0), repeater.get_id());
+    // The sign has no text, so it's left out instead of failing the whole schematic
+    assert!(cb.block_entities.is_empty());
+
+    let mut sign = HashMap::new();
+    sign.insert("Id".to_owned(), Value::String("minecraft:sign".to_owned()));
+    for row in &["Text1", "Text2", "Text3", "Text4"] {
+        sign.insert(
+            (*row).to_owned(),
+            Value::String(r#"{"text":""}"#.to_owned()),
+        );
+    }
+    sign.insert("Pos".to_owned(), Value::IntArray(vec![0, 5, 0]));
+    let mut nbt = schematic("minecraft:glass", vec![0, 0, 0]);
+    nbt.insert("BlockEntities", Value::List(vec![Value::Compound(sign)]))
+        .unwrap();
+    let cb = WorldEditClipboard::from_schematic_nbt(&nbt).unwrap();
+    // The sign is above the only block of the schematic
+    assert!(cb.block_entities.is_empty());
+
+    let nbt = schematic("minecraft:glass", vec![]);
+    assert!(matches!(
+        WorldEditClipboard::from_schematic_nbt(&nbt),
+        Err(SchematicLoadError::InvalidSchematic)
+    ));
+}
+
+#[test]
+fn reject_schematics_without_blocks_or_too_many() {
+    use nbt::Value;
+    let schematic = |width, height, length| {
+        let mut nbt = nbt::Blob::new();
+        nbt.insert("Version", Value::Int(2)).unwrap();
+        nbt.insert("Width", Value::Short(width)).unwrap();
+        nbt.insert("Height", Value::Short(height)).unwrap();
+        nbt.insert("Length", Value::Short(length)).unwrap();
+        nbt.insert("Palette", Value::Compound(HashMap::new()))
+            .unwrap();
+        nbt.insert("BlockData", Value::ByteArray(Vec::new()))
+            .unwrap();
+        let mut data = Vec::new();
+        nbt.to_gzip_writer(&mut data).unwrap();
+        WorldEditClipboard::from_schematic_bytes(&data)
+    };
+    assert!(matches!(
+        schematic(i16::MAX, i16::MAX, i16::MAX),
+        Err(SchematicLoadError::TooLarge(_))
+    ));
+    assert!(matches!(
+        schematic(-1, 4, 4),
+        Err(SchematicLoadError::InvalidSchematic)
+    ));
+    assert!(matches!(
+        schematic(4, 0, 4),
+        Err(SchematicLoadError::InvalidSchematic)
+    ));
+}
+
+#[test]
+fn reject_nbt_with_oversized_lengths() {
+    // A root compound holding a long array that claims to have i32::MAX entries
+    let mut data = vec![10, 0, 0, 12, 0, 1, b'a'];
+    data.extend_from_slice(&i32::MAX.to_be_bytes());
+    data.push(0);
+    assert!(check_nbt_lengths(&data).is_none());
+    assert!(matches!(
+        WorldEditClipboard::from_schematic_bytes(&data),
+        Err(SchematicLoadError::InvalidSchematic)
+    ));
+
+    let mut nbt = nbt::Blob::new();
+    nbt.insert("Width", nbt::Value::Short(2)).unwrap();
+    nbt.insert("Palette", nbt::Value::Compound(HashMap::new()))
+        .unwrap();
+    nbt.insert("Data", nbt::Value::LongArray(vec![1, 2, 3]))
+        .unwrap();
+    let mut data = Vec::new();
+    nbt.to_writer(&mut data).unwrap();
+    assert!(check_nbt_lengths(&data).is_some());
+}
+
+#[test]
+fn load_legacy_schematic_without_optional_keys() {
+    use nbt::Value;
+    let mut nbt = nbt::Blob::new();
+    nbt.insert("Width", Value::Short(1)).unwrap();
+    nbt.insert("Height", Value::Short(2)).unwrap();
+    nbt.insert("Length", Value::Short(1)).unwrap();
+    nbt.insert("Blocks", Value::ByteArray(vec![20, 35]))
+        .unwrap();
+    nbt.insert("Data", Value::ByteArray(vec![0, 14])).unwrap();
+
+    let cb = WorldEditClipboard::from_schematic_nbt(&nbt).unwrap();
+    assert_eq!((cb.offset_x, cb.offset_y, cb.offset_z), (0, 0, 0));
+    assert!(cb.block_entities.is_empty());
+    assert_eq!(cb.data.get_entry(0), Block::Glass {}.get_id());
+    assert_eq!(
+        cb.data.get_entry(1),
+        Block::from_name("red_wool").unwrap().get_id()
+    );
+}
+
+#[test]
+fn schematic_path_stays_in_folder() {
+    assert_eq!(
+        schematic_path("builds/adder.schem"),
+        Some(PathBuf::from("builds/adder.schem"))
+    );
+    assert_eq!(schematic_path("../Config.toml"), None);
+    assert_eq!(schematic_path("builds/../../world"), None);
+    assert_eq!(schematic_path("/etc/passwd"), None);
+    assert_eq!(schematic_path(""), None);
+}
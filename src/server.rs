@@ -1,19 +1,20 @@
 use crate::chat::ChatComponent;
 use crate::config::CONFIG;
 use crate::network::packets::clientbound::{
-    C00DisconnectLogin, C00Response, C01Pong, C02LoginSuccess, C03SetCompression, C13WindowItems,
-    C17PluginMessage, C24JoinGame, C24JoinGameBiomeEffects, C24JoinGameBiomeEffectsMoodSound,
+    C00DisconnectLogin, C00Response, C01Pong, C02LoginSuccess, C03SetCompression, C17PluginMessage,
+    C24JoinGame, C24JoinGameBiomeEffects, C24JoinGameBiomeEffectsMoodSound,
     C24JoinGameBiomeElement, C24JoinGameDimensionCodec, C24JoinGameDimensionElement, C32PlayerInfo,
-    C32PlayerInfoAddPlayer, C34PlayerPositionAndLook, C3FHeldItemChange, C4ETimeUpdate,
-    ClientBoundPacket,
+    C32PlayerInfoAddPlayer, C34PlayerPositionAndLook, C3FHeldItemChange, C42SpawnPosition,
+    C4ETimeUpdate, ClientBoundPacket,
 };
 use crate::network::packets::serverbound::{
     S00Handshake, S00LoginStart, S00Request, S01Ping, ServerBoundPacketHandler,
 };
-use crate::network::packets::{PacketEncoderExt, SlotData};
-use crate::network::{NetworkServer, NetworkState};
+use crate::network::packets::PacketEncoderExt;
+use crate::network::{NetworkServer, NetworkState, PROTOCOL_VERSION};
 use crate::player::{Gamemode, Player};
 use crate::plot::{self, commands::DECLARE_COMMANDS, database, Plot};
+use crate::world::BIOMES;
 use backtrace::Backtrace;
 use bus::Bus;
 use fern::colors::{Color, ColoredLevelConfig};
@@ -337,48 +338,32 @@ impl MinecraftServer {
                         infiniburn: "".to_owned(),
                     }
                 },
-                biomes: map! {
-                    "mchprs:plot".to_owned() => C24JoinGameBiomeElement {
-                        precipitation: "none".to_owned(),
-                        effects: C24JoinGameBiomeEffects {
-                            sky_color: 0x7BA4FF,
-                            water_fog_color: 0x050533,
-                            fog_color: 0xC0D8FF,
-                            water_color: 0x3F76E4,
-                            mood_sound: C24JoinGameBiomeEffectsMoodSound {
-                                tick_delay: 6000,
-                                offset: 2.0,
-                                sound: "minecraft:ambient.cave".to_owned(),
-                                block_search_extent: 8,
-                            }
-                        },
-                        depth: 0.1,
-                        temperature: 0.5,
-                        scale: 0.2,
-                        downfall: 0.5,
-                        category: "none".to_owned(),
-                    },
-                    "minecraft:plains".to_owned() => C24JoinGameBiomeElement {
-                        precipitation: "none".to_owned(),
-                        effects: C24JoinGameBiomeEffects {
-                            sky_color: 7907327,
-                            water_fog_color: 329011,
-                            fog_color: 12638463,
-                            water_color: 4159204,
-                            mood_sound: C24JoinGameBiomeEffectsMoodSound {
-                                tick_delay: 6000,
-                                offset: 2.0,
-                                sound: "minecraft:ambient.cave".to_owned(),
-                                block_search_extent: 8,
-                            }
-                        },
-                        depth: 0.125,
-                        temperature: 0.8,
-                        scale: 0.5,
-                        downfall: 0.4,
-                        category: "none".to_owned(),
-                    }
-                },
+                biomes: BIOMES
+                    .iter()
+                    .map(|biome| {
+                        let element = C24JoinGameBiomeElement {
+                            precipitation: "none".to_owned(),
+                            effects: C24JoinGameBiomeEffects {
+                                sky_color: biome.sky_color,
+                                water_fog_color: 0x050533,
+                                fog_color: 0xC0D8FF,
+                                water_color: 0x3F76E4,
+                                mood_sound: C24JoinGameBiomeEffectsMoodSound {
+                                    tick_delay: 6000,
+                                    offset: 2.0,
+                                    sound: "minecraft:ambient.cave".to_owned(),
+                                    block_search_extent: 8,
+                                },
+                            },
+                            depth: biome.depth,
+                            temperature: biome.temperature,
+                            scale: biome.scale,
+                            downfall: biome.downfall,
+                            category: "none".to_owned(),
+                        };
+                        (biome.name.to_owned(), element)
+                    })
+                    .collect(),
             },
             // this should be exactly the same has the dimension listed in dimension_codec
             dimension: C24JoinGameDimensionElement {
@@ -422,6 +407,15 @@ impl MinecraftServer {
         .encode();
         player.client.send_packet(&brand);
 
+        // Send the spawn position so the compass points to where the player joined
+        let spawn_position = C42SpawnPosition {
+            x: player.x.floor() as i32,
+            y: player.y.floor() as i32,
+            z: player.z.floor() as i32,
+        }
+        .encode();
+        player.client.send_packet(&spawn_position);
+
         // Send the player's position and rotation.
         let player_pos_and_look = C34PlayerPositionAndLook {
             x: player.x,
@@ -460,23 +454,7 @@ impl MinecraftServer {
         player.client.send_packet(&player_info);
 
         // Send the player's inventory
-        let slot_data: Vec<Option<SlotData>> = player
-            .inventory
-            .iter()
-            .map(|op| {
-                op.as_ref().map(|item| SlotData {
-                    item_count: item.count as i8,
-                    item_id: item.item_type.get_id() as i32,
-                    nbt: item.nbt.clone(),
-                })
-            })
-            .collect();
-        let window_items = C13WindowItems {
-            window_id: 0,
-            slot_data,
-        }
-        .encode();
-        player.client.send_packet(&window_items);
+        player.send_inventory();
 
         // Send the player's selected item slot
         let held_item_change = C3FHeldItemChange {
@@ -610,7 +588,8 @@ impl ServerBoundPacketHandler for MinecraftServer {
             2 => client.state = NetworkState::Login,
             _ => {}
         }
-        if client.state == NetworkState::Login && handshake.protocol_version != 754 {
+        client.protocol_version = handshake.protocol_version;
+        if client.state == NetworkState::Login && handshake.protocol_version != PROTOCOL_VERSION {
             warn!("A player tried to connect using the wrong version");
             let disconnect = C00DisconnectLogin {
                 reason: json!({
@@ -646,7 +625,7 @@ impl ServerBoundPacketHandler for MinecraftServer {
             json_response: json!({
                 "version": {
                     "name": "1.16.4",
-                    "protocol": 754
+                    "protocol": PROTOCOL_VERSION
                 },
                 "players": {
                     "max": CONFIG.max_players,
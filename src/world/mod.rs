@@ -47,6 +47,89 @@ pub trait World {
     fn pending_tick_at(&mut self, pos: BlockPos) -> bool;
 }
 
+/// A biome that players are told about when they join. There is no world generation, so
+/// biomes only change the colors of things like the sky, grass and leaves.
+pub struct Biome {
+    pub name: &'static str,
+    pub sky_color: i32,
+    pub temperature: f32,
+    pub downfall: f32,
+    pub depth: f32,
+    pub scale: f32,
+}
+
+/// All the biomes that chunks can use. The id of a biome is its index in this list.
+pub const BIOMES: &[Biome] = &[
+    Biome {
+        name: "mchprs:plot",
+        sky_color: 0x7BA4FF,
+        temperature: 0.5,
+        downfall: 0.5,
+        depth: 0.1,
+        scale: 0.2,
+    },
+    Biome {
+        name: "minecraft:plains",
+        sky_color: 7907327,
+        temperature: 0.8,
+        downfall: 0.4,
+        depth: 0.125,
+        scale: 0.5,
+    },
+    Biome {
+        name: "minecraft:desert",
+        sky_color: 7254527,
+        temperature: 2.0,
+        downfall: 0.0,
+        depth: 0.125,
+        scale: 0.05,
+    },
+    Biome {
+        name: "minecraft:forest",
+        sky_color: 7972607,
+        temperature: 0.7,
+        downfall: 0.8,
+        depth: 0.1,
+        scale: 0.2,
+    },
+    Biome {
+        name: "minecraft:jungle",
+        sky_color: 7842047,
+        temperature: 0.95,
+        downfall: 0.9,
+        depth: 0.1,
+        scale: 0.2,
+    },
+    Biome {
+        name: "minecraft:savanna",
+        sky_color: 7711487,
+        temperature: 1.2,
+        downfall: 0.0,
+        depth: 0.125,
+        scale: 0.05,
+    },
+    Biome {
+        name: "minecraft:snowy_tundra",
+        sky_color: 8364543,
+        temperature: 0.0,
+        downfall: 0.5,
+        depth: 0.125,
+        scale: 0.05,
+    },
+];
+
+impl Biome {
+    /// Returns the id of the biome called `name`. The namespace is optional.
+    pub fn id_from_name(name: &str) -> Option<u8> {
+        BIOMES
+            .iter()
+            .position(|biome| {
+                biome.name == name || biome.name.split_once(':').map(|(_, n)| n) == Some(name)
+            })
+            .map(|id| id as u8)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TickPriority {
     Highest,
@@ -1,7 +1,7 @@
 use crate::blocks::{BlockEntity, BlockPos};
 use crate::network::packets::clientbound::{
-    C20ChunkData, C20ChunkDataSection, C3BMultiBlockChange, C3BMultiBlockChangeRecord,
-    ClientBoundPacket,
+    C20ChunkData, C20ChunkDataSection, C23UpdateLight, C3BMultiBlockChange,
+    C3BMultiBlockChangeRecord, ClientBoundPacket,
 };
 use crate::network::packets::PacketEncoder;
 use serde::{Deserialize, Serialize};
@@ -204,11 +204,34 @@ impl PalettedBitBuffer {
         }
     }
 
+    /// Sets every entry to `val` in one go. Since only one value is left, the buffer shrinks
+    /// back to the smallest size.
+    pub fn fill(&mut self, val: u32) {
+        self.data = BitBuffer::create(4, self.data.entries);
+        self.palette = vec![val];
+        self.max_entries = 16;
+        self.use_palette = true;
+    }
+
     pub fn entries(&self) -> usize {
         self.data.entries
     }
 }
 
+#[test]
+fn paletted_bitbuffer_fill() {
+    let mut buffer = PalettedBitBuffer::with_entries(4096);
+    // Enough different entries to switch to the global palette
+    for idx in 0..4096 {
+        buffer.set_entry(idx, idx as u32 % 300);
+    }
+    buffer.fill(1398);
+    assert!((0..4096).all(|idx| buffer.get_entry(idx) == 1398));
+    buffer.set_entry(5, 3);
+    assert_eq!(buffer.get_entry(5), 3);
+    assert_eq!(buffer.get_entry(6), 1398);
+}
+
 impl Default for PalettedBitBuffer {
     fn default() -> Self {
         Self::with_entries(4096)
@@ -243,6 +266,17 @@ impl ChunkSection {
         old_block != block
     }
 
+    /// Sets all the blocks in the section. Returns how many blocks were changed.
+    fn fill(&mut self, block: u32) -> usize {
+        let entries = self.buffer.entries();
+        let changed = (0..entries)
+            .filter(|&idx| self.buffer.get_entry(idx) != block)
+            .count();
+        self.buffer.fill(block);
+        self.block_count = if block == 0 { 0 } else { entries as u32 };
+        changed
+    }
+
     fn load(data: ChunkSectionData) -> ChunkSection {
         let loaded_longs = data.data.into_iter().map(|x| x as u64).collect();
         let bits_per_entry = data.bits_per_block as u8;
@@ -329,9 +363,31 @@ pub struct Chunk {
     pub x: i32,
     pub z: i32,
     pub block_entities: HashMap<BlockPos, BlockEntity>,
+    /// The biome ids of the 4x4 columns that the client stores biomes in, indexed by
+    /// `(z / 4) * 4 + x / 4`. Every column has the same biome from the bottom to the top.
+    pub biomes: [u8; 16],
 }
 
 impl Chunk {
+    /// Encodes the light of the chunk. Light isn't calculated on the server, so every section
+    /// (including the ones just below and above the world) gets full sky light.
+    pub fn encode_light_packet(&self) -> PacketEncoder {
+        // There are 18 sections with light, from y = -1 to y = 16
+        let mask = (1 << 18) - 1;
+        C23UpdateLight {
+            chunk_x: self.x,
+            chunk_z: self.z,
+            trust_edges: true,
+            sky_light_mask: mask,
+            block_light_mask: 0,
+            empty_sky_light_mask: 0,
+            empty_block_light_mask: mask,
+            sky_light_arrays: vec![vec![0xFF; 2048]; 18],
+            block_light_arrays: Vec::new(),
+        }
+        .encode()
+    }
+
     pub fn encode_packet(&self, full_chunk: bool) -> PacketEncoder {
         let mut heightmap_buffer = BitBuffer::create(9, 256);
         for x in 0..16 {
@@ -373,7 +429,8 @@ impl Chunk {
             // Use `bool_to_option` feature when stabalized
             // Tracking issue: https://github.com/rust-lang/rust/issues/80967
             biomes: if full_chunk {
-                Some(vec![0; 1024])
+                // The client stores a biome for every 4x4x4 cube, 64 of them high
+                Some((0..1024).map(|idx| self.biomes[idx & 0xF] as i32).collect())
             } else {
                 None
             },
@@ -433,6 +490,35 @@ impl Chunk {
         changed
     }
 
+    /// Sets all the blocks of the section at `section_y` and removes the block entities in it.
+    /// Returns how many blocks were changed, counting the ones that only lost their block entity.
+    pub fn fill_section(&mut self, section_y: u8, block_id: u32) -> usize {
+        let removed: Vec<BlockPos> = self
+            .block_entities
+            .keys()
+            .filter(|pos| pos.y >> 4 == section_y as i32)
+            .copied()
+            .collect();
+        let mut changed = 0;
+        for pos in removed {
+            if self.get_block(pos.x as u32, pos.y as u32, pos.z as u32) == block_id {
+                changed += 1;
+            }
+            self.block_entities.remove(&pos);
+        }
+        if block_id == 0 {
+            // Sections that are only air don't have to be stored
+            let section = self.sections.remove(&section_y);
+            return changed + section.map_or(0, |section| section.block_count as usize);
+        }
+        changed
+            + self
+                .sections
+                .entry(section_y)
+                .or_insert_with(ChunkSection::new)
+                .fill(block_id)
+    }
+
     pub fn get_block(&self, x: u32, y: u32, z: u32) -> u32 {
         let section_y = (y / 16) as u8;
         if let Some(section) = self.sections.get(&section_y) {
@@ -442,6 +528,15 @@ impl Chunk {
         }
     }
 
+    /// Sets the biome of the 4x4 column that contains the block column at `x` and `z`.
+    /// Returns true if the biome was changed.
+    pub fn set_biome(&mut self, x: u32, z: u32, biome: u8) -> bool {
+        let idx = (z as usize >> 2) * 4 + (x as usize >> 2);
+        let changed = self.biomes[idx] != biome;
+        self.biomes[idx] = biome;
+        changed
+    }
+
     pub fn get_block_entity(&self, pos: BlockPos) -> Option<&BlockEntity> {
         self.block_entities.get(&pos)
     }
@@ -471,6 +566,7 @@ impl Chunk {
                 .map(|(y, cs)| (y, ChunkSection::load(cs)))
                 .collect(),
             block_entities: chunk_data.block_entities,
+            biomes: [0; 16],
         }
     }
 
@@ -480,6 +576,7 @@ impl Chunk {
             x,
             z,
             block_entities: HashMap::new(),
+            biomes: [0; 16],
         }
     }
 
@@ -489,6 +586,7 @@ impl Chunk {
             x,
             z,
             block_entities: HashMap::new(),
+            biomes: [0; 16],
         };
 
         for ry in 0..layers {
@@ -496,22 +594,27 @@ impl Chunk {
                 for rz in 0..16 {
                     let block_x = (x << 4) | rx;
                     let block_z = (z << 4) | rz;
-
-                    if block_x % 256 == 0
-                        || block_z % 256 == 0
-                        || (block_x + 1) % 256 == 0
-                        || (block_z + 1) % 256 == 0
-                    {
-                        chunk.set_block(rx as u32, ry as u32, rz as u32, 4495); // Stone Bricks
-                    } else {
-                        chunk.set_block(rx as u32, ry as u32, rz as u32, 246); // Sandstone
-                    }
+                    let block = Chunk::generated_block(layers, block_x, ry, block_z);
+                    chunk.set_block(rx as u32, ry as u32, rz as u32, block);
                 }
             }
         }
         chunk
     }
 
+    /// Returns the id of the block that `generate` places at the given world coordinates.
+    /// The world is flat: `layers` layers of sandstone with stone bricks on the plot borders,
+    /// and air above.
+    pub fn generated_block(layers: i32, x: i32, y: i32, z: i32) -> u32 {
+        if y < 0 || y >= layers {
+            0 // Air
+        } else if x % 256 == 0 || z % 256 == 0 || (x + 1) % 256 == 0 || (z + 1) % 256 == 0 {
+            4495 // Stone Bricks
+        } else {
+            246 // Sandstone
+        }
+    }
+
     pub fn drain_multi_block(&mut self) -> Vec<C3BMultiBlockChange> {
         let mut packets = Vec::new();
         for (y, section) in &mut self.sections {